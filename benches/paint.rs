@@ -0,0 +1,72 @@
+//! Benchmarks of preparing and drawing representative guis.
+//! These open a (hidden) window, so they need a display.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use egui_raylib::{egui, RlEgui};
+use raylib::prelude::*;
+
+fn gallery(ctx: &egui::Context) {
+    egui::CentralPanel::default().show(ctx, |ui| {
+        let mut value = 0.5;
+        let mut checked = true;
+        let mut text = String::from("Some text");
+        ui.heading("Gallery");
+        ui.label("A label");
+        let _ = ui.button("A button");
+        ui.checkbox(&mut checked, "A checkbox");
+        ui.add(egui::Slider::new(&mut value, 0.0..=1.0));
+        ui.text_edit_singleline(&mut text);
+        ui.add(egui::ProgressBar::new(0.3));
+        ui.separator();
+        ui.hyperlink("https://github.com/emilk/egui");
+    });
+}
+
+fn long_list(ctx: &egui::Context) {
+    egui::CentralPanel::default().show(ctx, |ui| {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for i in 0..10_000 {
+                ui.label(format!("Item {i}"));
+            }
+        });
+    });
+}
+
+fn bench_gui(
+    c: &mut Criterion,
+    rl: &mut RaylibHandle,
+    thread: &RaylibThread,
+    name: &str,
+    run_ui: fn(&egui::Context),
+) {
+    let mut gui = RlEgui::default();
+    // The first frames upload fonts, which isn't the interesting part.
+    for _ in 0..3 {
+        gui.prepare(rl, thread, run_ui);
+        let mut d = rl.begin_drawing(thread);
+        gui.draw(&mut d);
+    }
+
+    c.bench_function(&format!("{name}/prepare"), |b| {
+        b.iter(|| gui.prepare(rl, thread, run_ui))
+    });
+    c.bench_function(&format!("{name}/prepare+draw"), |b| {
+        b.iter(|| {
+            gui.prepare(rl, thread, run_ui);
+            let mut d = rl.begin_drawing(thread);
+            gui.draw(&mut d);
+        })
+    });
+}
+
+fn benches(c: &mut Criterion) {
+    // Raylib can only be initialized once per process.
+    let (mut rl, thread) = raylib::init().size(800, 600).title("benchmark").build();
+    rl.set_window_state(WindowState::default().set_window_hidden(true));
+    bench_gui(c, &mut rl, &thread, "empty", |_| {});
+    bench_gui(c, &mut rl, &thread, "gallery", gallery);
+    bench_gui(c, &mut rl, &thread, "long_list", long_list);
+}
+
+criterion_group!(paint, benches);
+criterion_main!(paint);
@@ -0,0 +1,86 @@
+//! Conversion traits and geometry helpers, which depend on egui alone.
+//!
+//! Unlike the rest of the crate, this module is available without the `raylib` feature,
+//! for tooling that only needs to translate coordinates and input.
+//! Note that egui itself requires `std`, so neither this module nor the crate is `no_std`.
+
+use std::sync::Once;
+
+use egui::Vec2;
+
+/// A trait to convert between raylib and egui types.
+pub trait ConvertRE<T> {
+    /// Construct the corresponding type from this type's data.
+    fn convert(&self) -> T;
+}
+
+/// Extension of [ConvertRE], to name the target type inline where inference fails:
+/// ```ignore
+/// d.draw_line_v(a.convert_to::<Vector2>(), b.convert_to::<Vector2>(), color);
+/// ```
+pub trait ConvertTo {
+    /// Same as [ConvertRE::convert], with the target type as a parameter.
+    fn convert_to<T>(&self) -> T
+    where
+        Self: ConvertRE<T>,
+    {
+        self.convert()
+    }
+}
+
+impl<U: ?Sized> ConvertTo for U {}
+
+/// Add `delta` to the accumulated scroll `accum`, and return the scroll to emit for any axis whose accumulated movement reached `threshold`.
+/// Emitted axes are reset, the others keep accumulating. A non-positive `threshold` passes `delta` straight through.
+pub fn accumulate_scroll(accum: &mut Vec2, delta: Vec2, threshold: f32) -> Option<Vec2> {
+    if threshold <= 0.0 {
+        return (delta != Vec2::ZERO).then_some(delta);
+    }
+    *accum += delta;
+    let mut out = Vec2::ZERO;
+    if accum.x.abs() >= threshold {
+        out.x = accum.x;
+        accum.x = 0.0;
+    }
+    if accum.y.abs() >= threshold {
+        out.y = accum.y;
+        accum.y = 0.0;
+    }
+    (out != Vec2::ZERO).then_some(out)
+}
+
+/// Size of the monitor in points, given raylib's reported size in pixels.
+///
+/// Some headless and virtual displays report a zero size, in which case the window's size (in pixels) is used instead.
+/// A warning is logged the first time this happens.
+pub fn monitor_size(monitor: (i32, i32), window: Vec2, pixels_per_point: f32) -> Vec2 {
+    static WARN: Once = Once::new();
+    let size = if monitor.0 > 0 && monitor.1 > 0 {
+        Vec2::new(monitor.0 as f32, monitor.1 as f32)
+    } else {
+        WARN.call_once(|| {
+            eprintln!(
+                "egui-raylib: Monitor reports a size of {}x{}, using window size instead.",
+                monitor.0, monitor.1
+            )
+        });
+        window
+    };
+    size / pixels_per_point
+}
+
+/// Whether a key held for `held_frames` frames should emit a repeat event this frame.
+///
+/// This is a coarse, frame-based approximation of OS key repeat: it ignores the actual frame time,
+/// so the repeat rate changes with the frame rate.
+pub fn key_repeats(held_frames: u32, delay: u32, interval: u32) -> bool {
+    held_frames >= delay && (held_frames - delay).is_multiple_of(interval.max(1))
+}
+
+/// Mirror `rect` vertically within a target `height` units tall, so that its top edge ends up at the bottom.
+pub fn flip_rect_y(rect: egui::Rect, height: f32) -> egui::Rect {
+    egui::Rect::from_x_y_ranges(
+        rect.x_range(),
+        (height - rect.max.y)..=(height - rect.min.y),
+    )
+}
@@ -0,0 +1,32 @@
+//! The error type shared by the whole crate.
+
+use std::fmt;
+
+/// Errors that can occur while gathering input for, or painting egui.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EguiRaylibError {
+    /// Text could not be read from, or written to the clipboard.
+    Clipboard(String),
+    /// A texture could not be created on the GPU.
+    TextureUpload(String),
+    /// A shape that can't be drawn was met in strict mode.
+    UnsupportedShape(&'static str),
+}
+
+impl fmt::Display for EguiRaylibError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Clipboard(detail) => write!(f, "clipboard error: {detail}"),
+            Self::TextureUpload(detail) => write!(f, "failed to upload texture: {detail}"),
+            Self::UnsupportedShape(shape) => write!(f, "{shape} are not supported"),
+        }
+    }
+}
+
+impl std::error::Error for EguiRaylibError {}
+
+/// Log an error that doesn't stop the frame to stderr, after a line saying what it meant for the gui.
+#[cfg(feature = "raylib")]
+pub(crate) fn log_error(outcome: &str, error: &EguiRaylibError) {
+    eprintln!("egui-raylib: {outcome}\n\tdetail: {error}");
+}
@@ -8,20 +8,329 @@ use egui::Rect as egRect;
 use egui::{DroppedFile, Event, Key, Modifiers, Pos2, RawInput, Vec2, ViewportId, ViewportInfo};
 use raylib::ffi::{KeyboardKey, MouseButton};
 use raylib::prelude::Rectangle as rayRect;
+use raylib::prelude::Vector2;
 use raylib::RaylibHandle;
 
-use crate::util::ConvertRE;
+use crate::base::{accumulate_scroll, key_repeats, monitor_size};
+use crate::error::{log_error, EguiRaylibError};
+use crate::util::{physical_key, ConvertRE};
+
+/// Largest texture side egui is told to keep its textures (e.g. the font atlas) within, if [InputOptions::max_texture_size] is not set.
+/// Practically every desktop GPU (and most mobile ones) support at least this.
+pub const DEFAULT_MAX_TEXTURE_SIZE: usize = 2048;
+
+/// Factor by which a single zoom in (Ctrl and plus) magnifies the gui.
+pub const ZOOM_STEP: f32 = 1.1;
+
+/// Distance (in points) two fingers must move together, or apart, before they pan or pinch.
+/// Below it fingers resting on the screen don't make the content jitter.
+pub const TOUCH_SLOP: f32 = 8.0;
 
 /// Struct to store values
 pub struct InputOptions {
     /// 'Point' to _native pixel_ conversion ratio. 'Points' are `egui`'s logical pixels.
     pub native_pixels_per_point: f32,
-    /// Maximum texture size supported on GPU.
+    /// Follow the window's content scale (as set by the OS for its monitor) instead of [InputOptions::native_pixels_per_point],
+    /// so that the gui keeps its size when the window moves to a monitor of a different DPI, or the system scale changes.
+    pub follow_window_scale: bool,
+    /// Maximum texture size supported on GPU. If set, egui is told to keep its textures within it,
+    /// and larger uploads fail with [EguiRaylibError::TextureUpload].
+    /// Rlgl provides no way to query this, so if `None`, egui is told [DEFAULT_MAX_TEXTURE_SIZE], but no upload is refused.
     pub max_texture_size: Option<usize>,
     /// Region of window allocated for egui to use.
+    /// If set, pointer events are only sent while the pointer is within it, so that several contexts can share one window.
     pub region: Option<rayRect>,
+    /// Confine egui to the largest region of this aspect ratio (width / height) centered in the window, e.g. `16.0 / 9.0`
+    /// to keep the layout of a fixed-aspect game UI on ultrawide monitors. The region is recomputed every frame from the window's size,
+    /// and is used instead of [InputOptions::region].
+    pub letterbox_aspect: Option<f32>,
     /// Map raylib's non-character keys to their egui counterparts.
+    /// By default holds Enter, Backspace, Tab, Escape and the arrow keys, which egui's widgets need for navigation,
+    /// and for closing menus and popups.
     pub key_map: HashMap<KeyboardKey, Key>,
+    /// Invert both scroll axes. See [wheel_to_scroll_delta] for the default convention.
+    pub invert_scroll: bool,
+    /// Minimum accumulated wheel movement (in lines) along an axis before a scroll event is emitted.
+    /// Smaller movements are accumulated across frames, so that mice reporting tiny fractional deltas don't make lists creep.
+    /// Set to `0.0` to pass all wheel movement through as-is.
+    pub scroll_threshold: f32,
+    /// Send the left mouse button as egui's secondary button, and the right one as its primary button,
+    /// e.g. for left-handed users who haven't swapped the buttons in their OS.
+    pub swap_primary_secondary: bool,
+    /// Number of frames a key must be held before it starts repeating.
+    pub key_repeat_delay: u32,
+    /// Number of frames between repeats of a held key.
+    pub key_repeat_interval: u32,
+    /// Repeat held keys at the OS's key-repeat delay and rate, as reported by raylib, instead of
+    /// [InputOptions::key_repeat_delay] and [InputOptions::key_repeat_interval].
+    /// Off by default, since raylib only reports repeats where its platform layer does (e.g. on desktop through GLFW);
+    /// elsewhere keys won't repeat at all with this set. Typed text always repeats at the OS's rate.
+    pub system_key_repeat: bool,
+    /// Size of the OS window decorations (title bar, borders) around the client area, in pixels.
+    /// Raylib provides no way to query these, so they're assumed to be absent unless set here.
+    /// Ignored for undecorated windows.
+    pub window_frame: Option<egui::Margin>,
+    /// Send [InputOptions::pointer_interpolation_steps] extra pointer positions along the straight line from where the mouse was
+    /// in the last frame to where it is now, so that fast strokes (e.g. on a canvas) don't show gaps at low frame rates.
+    /// Raylib doesn't keep the positions in between, so this is only an approximation. Off by default.
+    pub interpolate_pointer: bool,
+    /// Number of pointer positions sent in between frames when [InputOptions::interpolate_pointer] is set.
+    pub pointer_interpolation_steps: u32,
+}
+
+/// Summary of the input egui consumed in the last frame, so that the rest of the app can ignore it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InputCapture {
+    /// egui is using the pointer, e.g. it is hovering over a window, or dragging a widget.
+    pub pointer: bool,
+    /// egui is using the keyboard, e.g. a text edit has focus.
+    pub keyboard: bool,
+}
+
+impl InputOptions {
+    /// The maximum side length egui is told to keep textures within, as configured or [DEFAULT_MAX_TEXTURE_SIZE] otherwise.
+    pub fn max_texture_side(&self) -> usize {
+        self.max_texture_size.unwrap_or(DEFAULT_MAX_TEXTURE_SIZE)
+    }
+
+    /// The region egui is confined to in a window of `window_size` (in points), see [InputOptions::letterbox_aspect].
+    pub fn current_region(&self, window_size: Vec2) -> Option<rayRect> {
+        match self.letterbox_aspect {
+            Some(aspect) => Some(letterbox(window_size, aspect)),
+            None => self.region,
+        }
+    }
+
+    /// The egui buttons sent for the left and right mouse buttons, see [InputOptions::swap_primary_secondary].
+    pub(crate) fn left_right_buttons(&self) -> (egui::PointerButton, egui::PointerButton) {
+        if self.swap_primary_secondary {
+            (egui::PointerButton::Secondary, egui::PointerButton::Primary)
+        } else {
+            (egui::PointerButton::Primary, egui::PointerButton::Secondary)
+        }
+    }
+}
+
+/// Which physical modifier keys are held down.
+/// Egui's [Modifiers] don't tell the left and right keys apart, this does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ModifierKeys {
+    /// Left shift key.
+    pub left_shift: bool,
+    /// Right shift key.
+    pub right_shift: bool,
+    /// Left control key.
+    pub left_ctrl: bool,
+    /// Right control key.
+    pub right_ctrl: bool,
+    /// Left alt (option) key.
+    pub left_alt: bool,
+    /// Right alt (option) key.
+    pub right_alt: bool,
+    /// Left super (command, windows) key.
+    pub left_super: bool,
+    /// Right super (command, windows) key.
+    pub right_super: bool,
+}
+
+impl ModifierKeys {
+    /// Read the modifier keys currently held down.
+    pub fn read(rl: &RaylibHandle) -> Self {
+        Self {
+            left_shift: rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT),
+            right_shift: rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT),
+            left_ctrl: rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL),
+            right_ctrl: rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL),
+            left_alt: rl.is_key_down(KeyboardKey::KEY_LEFT_ALT),
+            right_alt: rl.is_key_down(KeyboardKey::KEY_RIGHT_ALT),
+            left_super: rl.is_key_down(KeyboardKey::KEY_LEFT_SUPER),
+            right_super: rl.is_key_down(KeyboardKey::KEY_RIGHT_SUPER),
+        }
+    }
+
+    /// Read the modifier keys released since the last frame.
+    pub fn read_released(rl: &RaylibHandle) -> Self {
+        Self {
+            left_shift: rl.is_key_released(KeyboardKey::KEY_LEFT_SHIFT),
+            right_shift: rl.is_key_released(KeyboardKey::KEY_RIGHT_SHIFT),
+            left_ctrl: rl.is_key_released(KeyboardKey::KEY_LEFT_CONTROL),
+            right_ctrl: rl.is_key_released(KeyboardKey::KEY_RIGHT_CONTROL),
+            left_alt: rl.is_key_released(KeyboardKey::KEY_LEFT_ALT),
+            right_alt: rl.is_key_released(KeyboardKey::KEY_RIGHT_ALT),
+            left_super: rl.is_key_released(KeyboardKey::KEY_LEFT_SUPER),
+            right_super: rl.is_key_released(KeyboardKey::KEY_RIGHT_SUPER),
+        }
+    }
+
+    /// The keys held in either `self` or `other`.
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            left_shift: self.left_shift || other.left_shift,
+            right_shift: self.right_shift || other.right_shift,
+            left_ctrl: self.left_ctrl || other.left_ctrl,
+            right_ctrl: self.right_ctrl || other.right_ctrl,
+            left_alt: self.left_alt || other.left_alt,
+            right_alt: self.right_alt || other.right_alt,
+            left_super: self.left_super || other.left_super,
+            right_super: self.right_super || other.right_super,
+        }
+    }
+
+    /// Egui's modifiers for these keys, where either side counts.
+    /// The command modifier is super on macOS, and ctrl everywhere else.
+    pub fn modifiers(&self) -> Modifiers {
+        let ctrl = self.left_ctrl || self.right_ctrl;
+        let mac_cmd = cfg!(target_os = "macos") && (self.left_super || self.right_super);
+        Modifiers {
+            alt: self.left_alt || self.right_alt,
+            ctrl,
+            shift: self.left_shift || self.right_shift,
+            mac_cmd,
+            command: if cfg!(target_os = "macos") {
+                mac_cmd
+            } else {
+                ctrl
+            },
+        }
+    }
+}
+
+/// Input state carried across frames.
+#[derive(Default)]
+pub struct InputState {
+    /// Wheel movement that hasn't yet crossed [InputOptions::scroll_threshold].
+    scroll_accum: Vec2,
+    /// Number of frames each mapped key has been held down for.
+    held_frames: HashMap<KeyboardKey, u32>,
+    /// Whether the pointer was outside [InputOptions::region] in the last frame.
+    pointer_outside: bool,
+    /// Pointer position (in points) last sent to egui, `None` once it left the region.
+    last_pointer: Option<Pos2>,
+    /// Modifier keys held down in the last frame.
+    modifier_keys: ModifierKeys,
+    /// Native pixels per point sent to egui in the last frame, if any.
+    native_pixels_per_point: Option<f32>,
+    /// Zoom factor asked for with the keyboard in the last frame, see [ZOOM_STEP].
+    keyboard_zoom: Option<f32>,
+    /// The two-finger gesture in progress, if any.
+    touch_gesture: Option<TouchGesture>,
+    /// Whether the pointer is ignored until all fingers are lifted, since a gesture took it over.
+    touch_suppresses_pointer: bool,
+    /// Position (in points) of each finger on the screen in the last frame, by raylib's id.
+    touch_points: HashMap<i32, Pos2>,
+}
+
+/// A two-finger touch gesture, which pans (scrolls) and pinches (zooms).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct TouchGesture {
+    /// Raylib's ids of the two fingers.
+    ids: (i32, i32),
+    /// Midpoint between the fingers and their distance when the gesture began, in points.
+    start: (Pos2, f32),
+    /// Midpoint between the fingers and their distance in the last frame, in points.
+    last: (Pos2, f32),
+    /// Whether the fingers have moved together further than [TOUCH_SLOP].
+    panning: bool,
+    /// Whether the fingers have moved apart (or together) further than [TOUCH_SLOP].
+    pinching: bool,
+}
+
+/// Push an [Event::Touch] for each finger that came down, moved or was lifted since the `last` frame,
+/// given the ids and positions (in points) of the fingers on the screen now. `last` is updated to match.
+///
+/// Raylib reports no pressure, so `force` is always `None`, which egui takes as unknown.
+pub(crate) fn touch_events(
+    last: &mut HashMap<i32, Pos2>,
+    current: &[(i32, Pos2)],
+    events: &mut Vec<Event>,
+) {
+    let touch = |id: i32, phase, pos| Event::Touch {
+        device_id: egui::TouchDeviceId(0),
+        id: egui::TouchId(id as u64),
+        phase,
+        pos,
+        force: None,
+    };
+    for &(id, pos) in current {
+        match last.insert(id, pos) {
+            None => events.push(touch(id, egui::TouchPhase::Start, pos)),
+            Some(before) if before != pos => events.push(touch(id, egui::TouchPhase::Move, pos)),
+            Some(_) => {}
+        }
+    }
+    last.retain(|&id, &mut pos| {
+        let down = current.iter().any(|&(c, _)| c == id);
+        if !down {
+            events.push(touch(id, egui::TouchPhase::End, pos));
+        }
+        down
+    });
+}
+
+/// Push the scroll and zoom events for two `fingers` on the screen (their ids and positions in points),
+/// and update the `gesture` in progress. `None` means fewer than two fingers, which ends the gesture.
+///
+/// A gesture only pans or pinches once it moved past [TOUCH_SLOP], and then by the motion since the last frame,
+/// so that it never jumps. Changing fingers (e.g. lifting one of three) starts over from their new positions, but keeps panning or pinching.
+pub(crate) fn touch_gesture_events(
+    gesture: &mut Option<TouchGesture>,
+    fingers: Option<[(i32, Pos2); 2]>,
+    modifiers: Modifiers,
+    events: &mut Vec<Event>,
+) {
+    let Some([(id0, p0), (id1, p1)]) = fingers else {
+        *gesture = None;
+        return;
+    };
+    let current = (p0.lerp(p1, 0.5), p0.distance(p1));
+    let g = match gesture {
+        Some(g) if g.ids == (id0, id1) => g,
+        _ => {
+            let (panning, pinching) = gesture.map_or((false, false), |g| (g.panning, g.pinching));
+            *gesture = Some(TouchGesture {
+                ids: (id0, id1),
+                start: current,
+                last: current,
+                panning,
+                pinching,
+            });
+            return;
+        }
+    };
+    g.panning |= current.0.distance(g.start.0) > TOUCH_SLOP;
+    g.pinching |= (current.1 - g.start.1).abs() > TOUCH_SLOP;
+    let pan = current.0 - g.last.0;
+    if g.panning && pan != Vec2::ZERO {
+        // Content follows the fingers, so there's no inverting this.
+        events.push(Event::MouseWheel {
+            unit: egui::MouseWheelUnit::Point,
+            delta: pan,
+            modifiers,
+        });
+    }
+    if g.pinching && g.last.1 > 0.0 && current.1 != g.last.1 {
+        events.push(Event::Zoom(current.1 / g.last.1));
+    }
+    g.last = current;
+}
+
+impl InputState {
+    /// Which modifier keys were held down when input was last gathered.
+    pub fn modifier_keys(&self) -> ModifierKeys {
+        self.modifier_keys
+    }
+
+    /// Zoom factor asked for with Ctrl and plus or minus in the last frame.
+    /// Unlike [Event::Zoom]s from pinching, which widgets under the pointer (e.g. plots) take up, these zoom the whole gui:
+    /// they are not sent to egui as events, but applied with [crate::RlEgui::set_zoom].
+    pub(crate) fn keyboard_zoom(&self) -> Option<f32> {
+        self.keyboard_zoom
+    }
+
+    /// The native pixels per point input was last gathered at, see [InputOptions::follow_window_scale].
+    pub fn native_pixels_per_point(&self) -> Option<f32> {
+        self.native_pixels_per_point
+    }
 }
 
 impl Default for InputOptions {
@@ -34,102 +343,332 @@ impl Default for InputOptions {
         key_map.insert(KeyboardKey::KEY_LEFT, Key::ArrowLeft);
         key_map.insert(KeyboardKey::KEY_RIGHT, Key::ArrowRight);
         key_map.insert(KeyboardKey::KEY_TAB, Key::Tab);
+        key_map.insert(KeyboardKey::KEY_ESCAPE, Key::Escape);
         Self {
             native_pixels_per_point: 1.0,
+            follow_window_scale: false,
             max_texture_size: None,
             region: None,
+            letterbox_aspect: None,
             key_map,
+            invert_scroll: false,
+            swap_primary_secondary: false,
+            scroll_threshold: 0.0,
+            key_repeat_delay: 30,
+            key_repeat_interval: 3,
+            system_key_repeat: false,
+            window_frame: None,
+            interpolate_pointer: false,
+            pointer_interpolation_steps: 4,
         }
     }
 }
 
+/// Convert raylib's mouse wheel movement into egui's scroll delta.
+///
+/// egui expects positive `y` to scroll towards the top of the content (i.e. the content moves down),
+/// and positive `x` to scroll towards its left edge.
+/// Raylib reports positive `y` when the wheel is rolled away from the user and positive `x` when it is tilted left,
+/// so the natural mapping is the identity: rolling the wheel up reveals the content above.
+/// If `invert` is set, both axes are negated.
+pub(crate) fn wheel_to_scroll_delta(wheel: Vector2, invert: bool) -> Vec2 {
+    let delta = Vec2::new(wheel.x, wheel.y);
+    if invert {
+        -delta
+    } else {
+        delta
+    }
+}
+
+/// Compute the `(inner, outer)` rects of a window whose client area is at `position` with `size`, both in pixels.
+///
+/// The outer rect is the inner rect expanded by the decoration `frame`, if any. Both are returned in points.
+pub(crate) fn window_rects(
+    position: Vector2,
+    size: Vector2,
+    frame: Option<egui::Margin>,
+    pixels_per_point: f32,
+) -> (egRect, egRect) {
+    let inner = egRect::from_min_size(Pos2::new(position.x, position.y), Vec2::new(size.x, size.y));
+    let outer = frame.map_or(inner, |m| inner + m);
+    let to_points = |r: egRect| {
+        egRect::from_min_max(
+            (r.min.to_vec2() / pixels_per_point).to_pos2(),
+            (r.max.to_vec2() / pixels_per_point).to_pos2(),
+        )
+    };
+    (to_points(inner), to_points(outer))
+}
+
+/// The largest rect with an `aspect` ratio (width / height) that fits in `size`, centered in it.
+pub(crate) fn letterbox(size: Vec2, aspect: f32) -> rayRect {
+    let (width, height) = if size.x > size.y * aspect {
+        (size.y * aspect, size.y)
+    } else {
+        (size.x, size.x / aspect)
+    };
+    rayRect::new(
+        (size.x - width) / 2.0,
+        (size.y - height) / 2.0,
+        width,
+        height,
+    )
+}
+
+/// Whether the pointer at `pos` (in points) is within `region`. Always true without a region.
+pub(crate) fn pointer_in_region(pos: Pos2, region: Option<rayRect>) -> bool {
+    region.map_or(true, |r| {
+        let r: egRect = r.convert();
+        r.contains(pos)
+    })
+}
+
+/// Push the pointer motion events for a mouse at `position` that moved by `delta` since the last frame, both in pixels.
+///
+/// `PointerMoved` always carries the absolute position, and is what widgets (sliders, drag values, scroll bars) use for dragging.
+/// `MouseMoved` carries the relative motion, which egui only exposes through `PointerState::motion`;
+/// it is emitted only when the mouse actually moved. Both are in points.
+/// If it moved, `steps` more evenly spaced `PointerMoved` events along the way come first, see [InputOptions::interpolate_pointer].
+pub(crate) fn pointer_motion_events(
+    position: Vector2,
+    delta: Vector2,
+    pixels_per_point: f32,
+    steps: u32,
+    events: &mut Vec<Event>,
+) {
+    if delta.x != 0.0 || delta.y != 0.0 {
+        events.push(Event::MouseMoved(Vec2::new(
+            delta.x / pixels_per_point,
+            delta.y / pixels_per_point,
+        )));
+        let start = position - delta;
+        for i in 1..=steps {
+            let t = i as f32 / (steps + 1) as f32;
+            events.push(Event::PointerMoved(Pos2::new(
+                (start.x + delta.x * t) / pixels_per_point,
+                (start.y + delta.y * t) / pixels_per_point,
+            )));
+        }
+    }
+    events.push(Event::PointerMoved(Pos2::new(
+        position.x / pixels_per_point,
+        position.y / pixels_per_point,
+    )));
+}
+
+/// Whether the pointer must be sent to egui, having moved to `pos` (in points) by `delta` (in pixels) since it was `last` sent, which is then updated.
+/// A still pointer sends nothing, since egui repaints on any event, and an event-driven app would never go idle.
+pub(crate) fn pointer_moved(last: &mut Option<Pos2>, pos: Pos2, delta: Vector2) -> bool {
+    if *last == Some(pos) && delta.x == 0.0 && delta.y == 0.0 {
+        return false;
+    }
+    *last = Some(pos);
+    true
+}
+
+/// Push the events for a pointer `button` at `pos` (in points) that was `pressed` and/or `released` this frame, and is `down` now.
+///
+/// A click shorter than a frame (easily done on a touchpad, or with a fast mouse) is both pressed and released,
+/// and must be sent as a press followed by a release, or egui would miss it. If the button is down again already,
+/// it was released first instead. Raylib's frame-edge queries only report both where its platform layer does,
+/// otherwise such a click is lost before it gets here.
+pub(crate) fn button_events(
+    button: egui::PointerButton,
+    pos: Pos2,
+    pressed: bool,
+    released: bool,
+    down: bool,
+    modifiers: Modifiers,
+    events: &mut Vec<Event>,
+) {
+    let event = |pressed| Event::PointerButton {
+        pos,
+        button,
+        pressed,
+        modifiers,
+    };
+    match (pressed, released) {
+        (true, true) if down => events.extend([event(false), event(true)]),
+        (true, true) => events.extend([event(true), event(false)]),
+        (true, false) => events.push(event(true)),
+        (false, true) => events.push(event(false)),
+        (false, false) => {}
+    }
+}
+
+/// Whether the pointer's motion is sent this frame: while it's `inside` the window (or region), or `captured` by a drag.
+/// Once it stops being tracked, `PointerGone` is pushed, and `pointer_outside` is set until it's tracked again.
+pub(crate) fn track_pointer(
+    inside: bool,
+    captured: bool,
+    pointer_outside: &mut bool,
+    events: &mut Vec<Event>,
+) -> bool {
+    let tracked = inside || captured;
+    if !tracked && !*pointer_outside {
+        events.push(Event::PointerGone);
+    }
+    *pointer_outside = !tracked;
+    tracked
+}
+
 fn get_mouse_input(
+    opt: &InputOptions,
+    state: &mut InputState,
     rl: &mut RaylibHandle,
     events: &mut Vec<Event>,
     pixels_per_point: f32,
     modifiers: Modifiers,
-    ctx: &egui::Context,
+    dragging: bool,
 ) {
-    let mouse_delta = rl.get_mouse_delta().scale_by(1.0 / pixels_per_point);
-    let mouse_position = rl.get_mouse_position().scale_by(1.0 / pixels_per_point);
-
-    if mouse_delta.x > 0.0 || mouse_delta.y > 0.0 || ctx.wants_pointer_input() {
-        events.push(Event::MouseMoved(Vec2::new(mouse_delta.x, mouse_delta.y)));
-        events.push(Event::PointerMoved(Pos2::new(
-            mouse_position.x,
-            mouse_position.y,
-        )));
+    let position = rl.get_mouse_position();
+    let pos = Pos2::new(position.x / pixels_per_point, position.y / pixels_per_point);
+    let window_size = Vec2::new(
+        rl.get_screen_width() as f32 / pixels_per_point,
+        rl.get_screen_height() as f32 / pixels_per_point,
+    );
+    let inside = pointer_in_region(pos, opt.current_region(window_size));
+    // A drag goes on outside the window (or region) until its button is released. Where raylib's platform layer keeps
+    // reporting the cursor outside the window while a button is held (e.g. GLFW on most desktops) the drag follows it,
+    // elsewhere the pointer stays where it left the window.
+    let captured = dragging
+        && (rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT)
+            || rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_RIGHT));
+    let delta = rl.get_mouse_delta();
+    if !track_pointer(inside, captured, &mut state.pointer_outside, events) {
+        state.last_pointer = None;
+    } else if pointer_moved(&mut state.last_pointer, pos, delta) {
+        let steps = if opt.interpolate_pointer {
+            opt.pointer_interpolation_steps
+        } else {
+            0
+        };
+        pointer_motion_events(position, delta, pixels_per_point, steps, events);
     }
+    let (left, right) = opt.left_right_buttons();
 
-    if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
-        let pos = rl.get_mouse_position();
-        let pos = Pos2::new(pos.x / pixels_per_point, pos.y / pixels_per_point);
-        events.push(Event::PointerButton {
+    // Presses only count within the region, but releases always do, so that buttons don't get stuck.
+    for (raylib_button, button) in [
+        (MouseButton::MOUSE_BUTTON_LEFT, left),
+        (MouseButton::MOUSE_BUTTON_RIGHT, right),
+    ] {
+        button_events(
+            button,
             pos,
-            button: egui::PointerButton::Primary,
-            pressed: true,
+            inside && rl.is_mouse_button_pressed(raylib_button),
+            rl.is_mouse_button_released(raylib_button),
+            rl.is_mouse_button_down(raylib_button),
             modifiers,
-        })
-    } else if rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) {
-        let pos = rl.get_mouse_position();
-        let pos = Pos2::new(pos.x / pixels_per_point, pos.y / pixels_per_point);
-        events.push(Event::PointerButton {
-            pos,
-            button: egui::PointerButton::Primary,
-            pressed: false,
-            modifiers,
-        })
+            events,
+        );
     }
 
-    if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) {
-        let pos = rl.get_mouse_position();
-        let pos = Pos2::new(pos.x / pixels_per_point, pos.y / pixels_per_point);
-        events.push(Event::PointerButton {
-            pos,
-            button: egui::PointerButton::Secondary,
-            pressed: true,
+    if !inside {
+        return;
+    }
+    wheel_events(opt, state, rl.get_mouse_wheel_move_v(), modifiers, events);
+}
+
+/// Push the scroll event for raylib's mouse `wheel` movement, once enough of it has added up, see [InputOptions::scroll_threshold].
+pub(crate) fn wheel_events(
+    opt: &InputOptions,
+    state: &mut InputState,
+    wheel: Vector2,
+    modifiers: Modifiers,
+    events: &mut Vec<Event>,
+) {
+    let wheel = wheel_to_scroll_delta(wheel, opt.invert_scroll);
+    if let Some(delta) = accumulate_scroll(&mut state.scroll_accum, wheel, opt.scroll_threshold) {
+        events.push(Event::MouseWheel {
+            unit: egui::MouseWheelUnit::Line,
+            delta,
             modifiers,
         })
-    } else if rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_RIGHT) {
-        let pos = rl.get_mouse_position();
-        let pos = Pos2::new(pos.x / pixels_per_point, pos.y / pixels_per_point);
-        events.push(Event::PointerButton {
-            pos,
-            button: egui::PointerButton::Secondary,
-            pressed: false,
-            modifiers,
+    }
+}
+
+fn get_touch_input(
+    opt: &InputOptions,
+    state: &mut InputState,
+    rl: &mut RaylibHandle,
+    events: &mut Vec<Event>,
+    pixels_per_point: f32,
+    modifiers: Modifiers,
+) {
+    let points: Vec<_> = (0..rl.get_touch_point_count())
+        .map(|i| {
+            let pos = rl.get_touch_position(i);
+            (
+                rl.get_touch_point_id(i),
+                Pos2::new(pos.x / pixels_per_point, pos.y / pixels_per_point),
+            )
         })
+        .collect();
+    touch_events(&mut state.touch_points, &points, events);
+    let touches = points.len();
+    let fingers = (touches >= 2).then(|| [points[0], points[1]]);
+    if let Some([(_, p0), (_, p1)]) = fingers {
+        let midpoint = p0.lerp(p1, 0.5);
+        // Pinches zoom around the pointer, so it follows the fingers.
+        events.push(Event::PointerMoved(midpoint));
+        state.last_pointer = Some(midpoint);
+        if !std::mem::replace(&mut state.touch_suppresses_pointer, true)
+            && rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT)
+        {
+            // The first finger pressed the pointer before the second one came down, so release it.
+            // Having moved to the midpoint first keeps egui from taking the release as a click.
+            events.push(Event::PointerButton {
+                pos: midpoint,
+                // Raylib reports touches as the left button.
+                button: opt.left_right_buttons().0,
+                pressed: false,
+                modifiers,
+            });
+        }
+    } else if touches == 0 {
+        state.touch_suppresses_pointer = false;
     }
+    touch_gesture_events(&mut state.touch_gesture, fingers, modifiers, events);
 }
 
 fn get_keyboard_input(
     opt: &InputOptions,
+    state: &mut InputState,
     rl: &mut RaylibHandle,
     events: &mut Vec<Event>,
     modifiers: Modifiers,
     ctx: &egui::Context,
 ) {
-    events.extend(opt.key_map.iter().filter_map(|(&kk, &key)| {
-        if rl.is_key_pressed(kk) {
-            Some(Event::Key {
-                key,
-                physical_key: None,
-                pressed: true,
-                repeat: false,
-                modifiers,
-            })
+    for (&kk, &key) in opt.key_map.iter() {
+        let (pressed, repeat) = if rl.is_key_pressed(kk) {
+            state.held_frames.insert(kk, 0);
+            (true, false)
         } else if rl.is_key_released(kk) {
-            Some(Event::Key {
-                key,
-                physical_key: None,
-                pressed: false,
-                repeat: false,
-                modifiers,
-            })
+            state.held_frames.remove(&kk);
+            (false, false)
+        } else if rl.is_key_down(kk) {
+            let frames = state.held_frames.entry(kk).or_insert(0);
+            *frames += 1;
+            let repeats = if opt.system_key_repeat {
+                rl.is_key_pressed_repeat(kk)
+            } else {
+                key_repeats(*frames, opt.key_repeat_delay, opt.key_repeat_interval)
+            };
+            if !repeats {
+                continue;
+            }
+            (true, true)
         } else {
-            None
-        }
-    }));
+            continue;
+        };
+        events.push(Event::Key {
+            key,
+            physical_key: physical_key(kk),
+            pressed,
+            repeat,
+            modifiers,
+        });
+    }
 
     // Egui actually wants Text input right now.
     if ctx.wants_keyboard_input() {
@@ -149,17 +688,113 @@ fn get_keyboard_input(
     }
 }
 
+/// Read the text on the clipboard.
+/// Invalid UTF-8 is replaced (with a warning), so that whatever text there is can still be pasted.
+/// The handle isn't used, but proves that the window (which owns the clipboard) exists.
+pub fn read_clipboard(_rl: &RaylibHandle) -> Result<String, EguiRaylibError> {
+    // Raylib's wrapper rejects invalid UTF-8 outright, so the clipboard is read directly.
+    // SAFETY: `_rl` shows that raylib's window, and so its platform layer, has been initialised.
+    let ptr = unsafe { raylib::ffi::GetClipboardText() };
+    if ptr.is_null() {
+        return Err(EguiRaylibError::Clipboard(
+            "clipboard is empty, or holds no text".to_owned(),
+        ));
+    }
+    // SAFETY: Raylib returns a nul-terminated string, which stays valid until the clipboard is next read.
+    let bytes = unsafe { std::ffi::CStr::from_ptr(ptr) }.to_bytes();
+    let (text, lossy) = clipboard_text(bytes);
+    if lossy {
+        log_error(
+            "Pasting clipboard text with its invalid parts replaced.",
+            &EguiRaylibError::Clipboard("text is not valid UTF-8".to_owned()),
+        );
+    }
+    Ok(text)
+}
+
+/// Decode clipboard contents into text to paste, and whether any invalid UTF-8 had to be replaced.
+/// Line breaks are kept, but normalized to `\n`.
+pub(crate) fn clipboard_text(bytes: &[u8]) -> (String, bool) {
+    let text = String::from_utf8_lossy(bytes);
+    let lossy = matches!(text, std::borrow::Cow::Owned(_));
+    (text.replace("\r\n", "\n"), lossy)
+}
+
+/// Describe a file dropped onto the window.
+/// Paths without a file name (e.g. a root directory) are named by the whole path instead.
+pub(crate) fn dropped_file(path: &str) -> DroppedFile {
+    let path = PathBuf::from(path);
+    let name = path
+        .file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned();
+    DroppedFile {
+        path: Some(path),
+        name,
+        mime: "application/octet-stream".to_owned(),
+        last_modified: None,
+        bytes: None,
+    }
+}
+
+/// Ask egui for another frame if the window was `resized`, since some layouts (e.g. panels sized to their content,
+/// or anything placed using the last frame's `available_rect`) only settle a frame after the screen rect changes.
+pub(crate) fn repaint_on_resize(ctx: &egui::Context, resized: bool) {
+    if resized {
+        ctx.request_repaint();
+    }
+}
+
 /// Using the provided input options, gather all required input for egui.
-/// `last_key` is simply an option to track the key pressed in previous frame, so that it's release event may be pushed..
-pub fn gather_input(opt: &InputOptions, ctx: &egui::Context, rl: &mut RaylibHandle) -> RawInput {
+/// `state` tracks input across frames, and should be the same for every call made for one context.
+pub fn gather_input(
+    opt: &InputOptions,
+    state: &mut InputState,
+    ctx: &egui::Context,
+    rl: &mut RaylibHandle,
+) -> RawInput {
+    gather_input_scaled(opt, state, ctx, rl, None)
+}
+
+/// Native pixels per point to gather input at: `scale` if given, else the `window_scale` if [InputOptions::follow_window_scale] is set,
+/// else [InputOptions::native_pixels_per_point].
+pub(crate) fn native_scale(opt: &InputOptions, scale: Option<f32>, window_scale: Vector2) -> f32 {
+    if let Some(scale) = scale {
+        scale
+    } else if opt.follow_window_scale {
+        // Both axes are scaled alike on every platform raylib supports.
+        window_scale.x
+    } else {
+        opt.native_pixels_per_point
+    }
+}
+
+/// Same as [gather_input], but at `native_pixels_per_point` instead of the options' (or window's) scale, if given.
+pub fn gather_input_scaled(
+    opt: &InputOptions,
+    state: &mut InputState,
+    ctx: &egui::Context,
+    rl: &mut RaylibHandle,
+    native_pixels_per_point: Option<f32>,
+) -> RawInput {
     let monitor_id = raylib::window::get_current_monitor();
     let (mw, mh) = (
         raylib::window::get_monitor_width(monitor_id),
         raylib::window::get_monitor_height(monitor_id),
     );
-    let pixels_per_point = ctx.zoom_factor() * opt.native_pixels_per_point;
+    let native_pixels_per_point =
+        native_scale(opt, native_pixels_per_point, rl.get_window_scale_dpi());
+    // On a change egui builds its fonts anew for the new scale, and sends the whole atlas again,
+    // so that text stays crisp without any further work here.
+    state.native_pixels_per_point = Some(native_pixels_per_point);
+    let pixels_per_point = ctx.zoom_factor() * native_pixels_per_point;
 
-    let monitor_size = Vec2::new(mw as f32 / pixels_per_point, mh as f32 / pixels_per_point);
+    let monitor_size = monitor_size(
+        (mw, mh),
+        Vec2::new(rl.get_screen_width() as f32, rl.get_screen_height() as f32),
+        pixels_per_point,
+    );
     let window_size = Some(egRect::from_min_max(
         Pos2::ZERO,
         Pos2::new(
@@ -168,67 +803,90 @@ pub fn gather_input(opt: &InputOptions, ctx: &egui::Context, rl: &mut RaylibHand
         ),
     ));
 
+    let frame = opt
+        .window_frame
+        .filter(|_| !rl.get_window_state().window_undecorated());
+    let (inner_rect, outer_rect) = window_rects(
+        rl.get_window_position(),
+        Vector2::new(rl.get_screen_width() as f32, rl.get_screen_height() as f32),
+        frame,
+        pixels_per_point,
+    );
+
     let viewport = ViewportInfo {
         parent: None,
         title: None,
         events: Default::default(),
-        native_pixels_per_point: Some(opt.native_pixels_per_point),
+        native_pixels_per_point: Some(native_pixels_per_point),
         monitor_size: Some(monitor_size),
-        inner_rect: window_size,
-        outer_rect: window_size,
+        inner_rect: Some(inner_rect),
+        outer_rect: Some(outer_rect),
         minimized: Some(rl.is_window_minimized()),
         maximized: None,
         fullscreen: Some(rl.is_window_fullscreen()),
         focused: Some(rl.is_window_focused()),
     };
 
-    let screen_rect = opt.region.map(|r| r.convert()).or(window_size);
-
-    let modifiers = Modifiers {
-        alt: rl.is_key_down(KeyboardKey::KEY_LEFT_ALT)
-            || rl.is_key_down(KeyboardKey::KEY_RIGHT_ALT),
-        ctrl: rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
-            || rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL),
-        shift: rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)
-            || rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT),
-        mac_cmd: false,
-        command: false,
-    };
+    let screen_rect = window_size.map(|w| opt.current_region(w.size()).map_or(w, |r| r.convert()));
+    repaint_on_resize(ctx, rl.is_window_resized());
+
+    state.modifier_keys = ModifierKeys::read(rl);
+    let modifiers = state.modifier_keys.modifiers();
+    // Raylib only knows where each key ended up after the frame's events, not their order.
+    // A modifier released in the same frame as another key was most likely held for it, so shortcuts keep it.
+    let chord_modifiers = state
+        .modifier_keys
+        .or(ModifierKeys::read_released(rl))
+        .modifiers();
 
     let mut events: Vec<_> = Vec::new();
 
-    get_keyboard_input(opt, rl, &mut events, modifiers, ctx);
+    get_keyboard_input(opt, state, rl, &mut events, chord_modifiers, ctx);
 
-    if rl.is_key_pressed(KeyboardKey::KEY_C) && modifiers.ctrl {
+    if rl.is_key_pressed(KeyboardKey::KEY_C) && chord_modifiers.command {
         events.push(Event::Copy)
-    } else if rl.is_key_pressed(KeyboardKey::KEY_V) && modifiers.ctrl {
-        match rl.get_clipboard_text() {
-			Ok(s) => events.push(Event::Paste(s)),
-			Err(e) => eprintln!("egui-raylib: Expect clipboard to have utf8 text, cannot paste otherwise\n\tdetail: {e}")
-		}
+    } else if rl.is_key_pressed(KeyboardKey::KEY_X) && chord_modifiers.command {
+        events.push(Event::Cut)
+    } else if rl.is_key_pressed(KeyboardKey::KEY_V) && chord_modifiers.command {
+        match read_clipboard(rl) {
+            Ok(s) => events.push(Event::Paste(s)),
+            Err(e) => log_error("Nothing to paste.", &e),
+        }
     }
 
-    get_mouse_input(rl, &mut events, pixels_per_point, modifiers, ctx);
+    // Plus shares its key with equals on most layouts.
+    state.keyboard_zoom = if chord_modifiers.command
+        && (rl.is_key_pressed(KeyboardKey::KEY_EQUAL) || rl.is_key_pressed(KeyboardKey::KEY_KP_ADD))
+    {
+        Some(ZOOM_STEP)
+    } else if chord_modifiers.command
+        && (rl.is_key_pressed(KeyboardKey::KEY_MINUS)
+            || rl.is_key_pressed(KeyboardKey::KEY_KP_SUBTRACT))
+    {
+        Some(1.0 / ZOOM_STEP)
+    } else {
+        None
+    };
+
+    get_touch_input(opt, state, rl, &mut events, pixels_per_point, modifiers);
+    if !state.touch_suppresses_pointer {
+        let dragging = ctx.dragged_id().is_some();
+        get_mouse_input(
+            opt,
+            state,
+            rl,
+            &mut events,
+            pixels_per_point,
+            modifiers,
+            dragging,
+        );
+    }
 
     let dropped_files = if rl.is_file_dropped() {
         rl.load_dropped_files()
             .paths()
             .iter()
-            .map(|&path| {
-                let path = PathBuf::from(path);
-                let name = path
-                    .file_name()
-                    .expect("Expect dropped file to have file name.")
-                    .to_string_lossy()
-                    .into_owned();
-                DroppedFile {
-                    path: Some(path),
-                    name,
-                    mime: "application/octet-stream".to_owned(),
-                    last_modified: None,
-                    bytes: None,
-                }
-            })
+            .map(|&path| dropped_file(path))
             .collect()
     } else {
         Vec::new()
@@ -238,10 +896,10 @@ pub fn gather_input(opt: &InputOptions, ctx: &egui::Context, rl: &mut RaylibHand
         viewport_id: ViewportId::ROOT,
         viewports: iter::once((ViewportId::ROOT, viewport)).collect(),
         screen_rect,
-        max_texture_side: None,
+        max_texture_side: Some(opt.max_texture_side()),
         time: Some(rl.get_time()),
         predicted_dt: 1.0 / 60.0,
-        modifiers: Modifiers::default(),
+        modifiers,
         events,
         hovered_files: Default::default(),
         dropped_files,
@@ -36,50 +36,246 @@
 //! }
 //! ```
 
-use input::{gather_input, InputOptions};
+#[cfg(feature = "raylib")]
+use input::{gather_input, gather_input_scaled, InputOptions, InputState};
+#[cfg(feature = "raylib")]
 use paint::{Painter, PlatformHandler};
+#[cfg(feature = "raylib")]
+use util::ConvertRE;
+#[cfg(feature = "raylib")]
 use raylib::{
-    drawing::{RaylibDraw, RaylibScissorModeExt},
+    drawing::{RaylibDraw, RaylibScissorModeExt, RaylibTextureModeExt},
     RaylibHandle, RaylibThread,
 };
 
 /// Re-export egui crate for convenience.
 pub use egui;
 
+pub mod base;
+pub mod error;
+#[cfg(feature = "raylib")]
 pub mod input;
+#[cfg(feature = "raylib")]
 pub mod paint;
+#[cfg(feature = "raylib")]
 pub mod util;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "raylib"))]
 mod tests;
 
+/// Range of zoom factors allowed by [RlEgui::set_zoom].
+pub const ZOOM_RANGE: std::ops::RangeInclusive<f32> = 0.5..=4.0;
+
 /// A no-op implementor for [paint::PlatformHandler]
+#[cfg(feature = "raylib")]
 pub struct DummyHandler;
 
+#[cfg(feature = "raylib")]
 impl paint::PlatformHandler for DummyHandler {
     fn open_url(&mut self, _url: egui::OpenUrl) {}
     fn output_events(&mut self, _vec: &[egui::output::OutputEvent]) {}
 }
 
-#[derive(Default)]
+/// A [paint::PlatformHandler] that logs widget focus and value changes to stderr.
+/// Handy for debugging focus flow, or as a starting point for basic accessibility hooks.
+#[cfg(feature = "raylib")]
+pub struct LoggingHandler;
+
+#[cfg(feature = "raylib")]
+impl paint::PlatformHandler for LoggingHandler {
+    fn open_url(&mut self, url: egui::OpenUrl) {
+        eprintln!("egui-raylib: Request to open url: {}", url.url);
+    }
+
+    fn output_events(&mut self, vec: &[egui::output::OutputEvent]) {
+        use egui::output::OutputEvent;
+        for event in vec {
+            match event {
+                OutputEvent::FocusGained(info) => {
+                    eprintln!("egui-raylib: Focus gained: {}", info.description())
+                }
+                OutputEvent::ValueChanged(info) => {
+                    eprintln!("egui-raylib: Value changed: {}", info.description())
+                }
+                OutputEvent::TextSelectionChanged(info) => {
+                    eprintln!("egui-raylib: Text selection changed: {}", info.description())
+                }
+                _ => { /* Clicks are not logged. */ }
+            }
+        }
+    }
+}
+
+/// A [paint::PlatformHandler] that resolves relative urls (e.g. `../index.html`, or `?search=label`) against a base url,
+/// before passing them on to another handler, e.g. to link to pages within the documentation of a help system.
+/// Absolute urls are passed on unchanged, as are all output events.
+#[cfg(feature = "raylib")]
+pub struct BaseUrlHandler<H> {
+    /// The absolute url relative urls are resolved against, e.g. `https://docs.rs/egui/latest/egui/`.
+    pub base: String,
+    /// The handler resolved urls are passed on to.
+    pub inner: H,
+}
+
+#[cfg(feature = "raylib")]
+impl<H> BaseUrlHandler<H> {
+    /// Resolve urls against `base`, and pass them on to `inner`.
+    pub fn new(base: impl Into<String>, inner: H) -> Self {
+        Self {
+            base: base.into(),
+            inner,
+        }
+    }
+}
+
+#[cfg(feature = "raylib")]
+impl<H: paint::PlatformHandler> paint::PlatformHandler for BaseUrlHandler<H> {
+    fn open_url(&mut self, url: egui::OpenUrl) {
+        self.inner.open_url(egui::OpenUrl {
+            url: paint::resolve_url(&self.base, &url.url),
+            ..url
+        });
+    }
+
+    fn output_events(&mut self, vec: &[egui::output::OutputEvent]) {
+        self.inner.output_events(vec);
+    }
+}
+
 /// A structure to simplify use of [egui] with [raylib]
+#[cfg(feature = "raylib")]
 pub struct RlEgui {
     /// The underlying [egui::Context] owned by this struct.
     pub ctx: egui::Context,
     inopt: InputOptions,
+    instate: InputState,
     prs: Option<paint::PreparedShapes>,
     painter: paint::Painter,
+    /// Thread stored for use by [RlEgui::prepare_stored].
+    thread: Option<RaylibThread>,
+    /// Set once egui sends [egui::ViewportCommand::Close].
+    close_requested: bool,
+    /// Whether the title and icon of egui's root viewport have been applied to the window.
+    chrome_applied: bool,
+    /// Whether the font atlas must be uploaded again, after [RlEgui::clear_textures].
+    reupload_font: bool,
+    /// Repaint delay requested by egui in the last prepared frame.
+    repaint_delay: std::time::Duration,
+    /// Whether the last prepared frame had input, asked to be repainted right away, or changed textures.
+    changed: bool,
+    /// When egui asked to be repainted by, for [RlEgui::run_event_driven].
+    repaint_deadline: Option<std::time::Instant>,
+    /// Target FPS last set by [RlEgui::run_event_driven], if any.
+    target_fps: Option<u32>,
+    /// Earliest time egui's repaint callback was asked to repaint by, see [RlEgui::needs_repaint].
+    repaint_requested: std::sync::Arc<std::sync::Mutex<Option<std::time::Instant>>>,
+    /// Whether the gui is rendered into [RlEgui::cache], and only redrawn when it changes.
+    cached_rendering: bool,
+    /// The gui as last rendered, when using cached rendering.
+    cache: Option<raylib::texture::RenderTexture2D>,
+    /// Output events of the last prepared frame, see [RlEgui::drain_output_events].
+    output_events: Vec<egui::output::OutputEvent>,
+    /// Whether egui may lock the cursor, see [RlEgui::set_cursor_grab].
+    cursor_grab: bool,
+    /// Whether egui asked for the cursor to be grabbed, using [egui::ViewportCommand::CursorGrab].
+    grab_requested: bool,
+    /// Whether the cursor is currently locked by egui.
+    cursor_locked: bool,
+    /// Whether egui hid the cursor, in this window.
+    cursor: paint::CursorState,
+    /// Whether a copy of each frame's input is kept, see [RlEgui::set_keep_raw_input].
+    keep_raw_input: bool,
+    /// The input sent to egui in the last prepared frame, if kept.
+    last_raw_input: Option<egui::RawInput>,
+    /// Texture changes of the frames run by [RlEgui::tessellate_now], to be uploaded with the next prepared frame.
+    deferred_textures: egui::TexturesDelta,
+    /// Whether prepared frames mark where each layer order begins, see [RlEgui::set_split_layers].
+    split_layers: bool,
 }
 
+#[cfg(feature = "raylib")]
+impl Default for RlEgui {
+    fn default() -> Self {
+        Self::new(InputOptions::default(), egui::Context::default())
+    }
+}
+
+#[cfg(feature = "raylib")]
 impl RlEgui {
     /// Constructor.
+    /// Installs a repaint callback on `ctx` (replacing any other), for [RlEgui::needs_repaint].
+    /// Also turns off egui's own Ctrl+Plus/Minus zoom, since [RlEgui::set_zoom] handles those keys.
     pub fn new(inopt: InputOptions, ctx: egui::Context) -> RlEgui {
+        // egui would zoom by its own steps and range on the same keys, on top of ours.
+        ctx.options_mut(|o| o.zoom_with_keyboard = false);
+        let repaint_requested = std::sync::Arc::new(std::sync::Mutex::new(None));
+        let requested = repaint_requested.clone();
+        ctx.set_request_repaint_callback(move |info| {
+            // egui forgets delayed repaints requested between frames (e.g. by other threads), so they are kept here.
+            let Some(by) = std::time::Instant::now().checked_add(info.delay) else {
+                return;
+            };
+            if let Ok(mut requested) = requested.lock() {
+                *requested = Some(requested.map_or(by, |r: std::time::Instant| r.min(by)));
+            }
+        });
         Self {
             ctx,
             inopt,
+            instate: InputState::default(),
             prs: None,
             painter: Painter::default(),
+            thread: None,
+            close_requested: false,
+            chrome_applied: false,
+            reupload_font: false,
+            repaint_delay: std::time::Duration::ZERO,
+            changed: false,
+            repaint_deadline: None,
+            target_fps: None,
+            repaint_requested,
+            cached_rendering: false,
+            cache: None,
+            output_events: Vec::new(),
+            cursor_grab: false,
+            grab_requested: false,
+            cursor_locked: false,
+            cursor: Default::default(),
+            keep_raw_input: false,
+            last_raw_input: None,
+            deferred_textures: Default::default(),
+            split_layers: false,
+        }
+    }
+
+    /// Constructor that also stores (a clone of) the raylib thread, so that [RlEgui::prepare_stored] may be used.
+    pub fn new_with_thread(inopt: InputOptions, ctx: egui::Context, thread: &RaylibThread) -> RlEgui {
+        Self {
+            thread: Some(thread.clone()),
+            ..Self::new(inopt, ctx)
+        }
+    }
+
+    /// Set up `ctx` before the first frame, e.g. its fonts, style or options. Meant to be chained onto the constructor:
+    /// `RlEgui::new(inopt, ctx).configure(|ctx| ctx.style_mut(|s| s.animation_time = 0.0))`.
+    /// Warns if called after a frame was prepared, since egui would have already built fonts and laid out a frame without it.
+    pub fn configure(self, configure: impl FnOnce(&egui::Context)) -> Self {
+        // The chrome is applied during the first frame.
+        if self.chrome_applied {
+            eprintln!("egui-raylib: RlEgui configured after the first frame, which was prepared without it.");
         }
+        configure(&self.ctx);
+        self
+    }
+
+    /// Use `fonts` from the first frame on. See [RlEgui::configure].
+    pub fn with_fonts(self, fonts: egui::FontDefinitions) -> Self {
+        self.configure(|ctx| ctx.set_fonts(fonts))
+    }
+
+    /// Use `style` from the first frame on. See [RlEgui::configure].
+    pub fn with_style(self, style: egui::Style) -> Self {
+        self.configure(|ctx| ctx.set_style(style))
     }
 
     /// Perform all pre-draw steps such as loading and freeing textures, and prepare the shapes to be drawn.
@@ -92,6 +288,8 @@ impl RlEgui {
     }
 
     /// Perform all pre-draw steps and prepare shapes to be drawn. Use the provided handler for handling platform events.
+    /// # Panics
+    /// If a texture could not be uploaded, see [RlEgui::try_prepare_with].
     pub fn prepare_with<F, H>(
         &mut self,
         rl: &mut RaylibHandle,
@@ -102,10 +300,798 @@ impl RlEgui {
         F: FnOnce(&egui::Context),
         H: PlatformHandler,
     {
-        let raw_input = gather_input(&self.inopt, &self.ctx, rl);
-        let output = paint::full_output(rl, raw_input, &self.ctx, run_ui, handler);
-        let prepared = self.painter.predraw(output, rl, rthread);
+        if let Err(e) = self.try_prepare_with(rl, rthread, run_ui, handler) {
+            panic!("egui-raylib: {e}");
+        }
+    }
+
+    /// Same as [RlEgui::prepare_with], but returns an error instead of panicking if a texture could not be uploaded.
+    /// The frame's shapes are not prepared then.
+    pub fn try_prepare_with<F, H>(
+        &mut self,
+        rl: &mut RaylibHandle,
+        rthread: &RaylibThread,
+        run_ui: F,
+        handler: &mut H,
+    ) -> Result<(), error::EguiRaylibError>
+    where
+        F: FnOnce(&egui::Context),
+        H: PlatformHandler,
+    {
+        self.try_prepare_scaled(rl, rthread, None, run_ui, handler)
+    }
+
+    /// Same as [RlEgui::try_prepare_with], but lays out and draws this frame at `native_pixels_per_point`
+    /// instead of [InputOptions::native_pixels_per_point] (or the window's scale), if given.
+    /// This lets e.g. a ui-scale slider animate the gui's size smoothly, without changing the input options every frame.
+    /// Egui's zoom factor still applies on top. Note that egui rasterizes its fonts anew for every new scale.
+    pub fn try_prepare_scaled<F, H>(
+        &mut self,
+        rl: &mut RaylibHandle,
+        rthread: &RaylibThread,
+        native_pixels_per_point: Option<f32>,
+        run_ui: F,
+        handler: &mut H,
+    ) -> Result<(), error::EguiRaylibError>
+    where
+        F: FnOnce(&egui::Context),
+        H: PlatformHandler,
+    {
+        // Requests made from here on (e.g. by a resize, or while this frame runs) are for the next one, so they must stay set.
+        self.take_repaint_request();
+        let raw_input = gather_input_scaled(
+            &self.inopt,
+            &mut self.instate,
+            &self.ctx,
+            rl,
+            native_pixels_per_point,
+        );
+        self.try_prepare_gathered(rl, rthread, raw_input, run_ui, handler)
+    }
+
+    /// Prepare a frame for input that was already gathered.
+    fn try_prepare_gathered<F, H>(
+        &mut self,
+        rl: &mut RaylibHandle,
+        rthread: &RaylibThread,
+        raw_input: egui::RawInput,
+        run_ui: F,
+        handler: &mut H,
+    ) -> Result<(), error::EguiRaylibError>
+    where
+        F: FnOnce(&egui::Context),
+        H: PlatformHandler,
+    {
+        if let Some(factor) = self.instate.keyboard_zoom() {
+            self.set_zoom(self.ctx.zoom_factor() * factor);
+        }
+        let has_input = !raw_input.events.is_empty();
+        if self.keep_raw_input {
+            self.last_raw_input = Some(raw_input.clone());
+        }
+        let split_layers = self.split_layers;
+        let run_ui = |ctx: &egui::Context| {
+            if split_layers {
+                paint::mark_layers(ctx);
+            }
+            run_ui(ctx)
+        };
+        let mut output =
+            paint::full_output(rl, raw_input, &self.ctx, run_ui, handler, &mut self.cursor);
+        self.output_events
+            .clone_from(&output.platform_output.events);
+        if !self.deferred_textures.is_empty() {
+            let newer = std::mem::replace(
+                &mut output.textures_delta,
+                std::mem::take(&mut self.deferred_textures),
+            );
+            output.textures_delta.append(newer);
+        }
+        // The font texture is stale if egui's atlas grew beyond it, e.g. as new glyphs were added.
+        let font_stale = self.painter.take_font_stale();
+        if std::mem::take(&mut self.reupload_font) || font_stale {
+            // egui only sends the whole atlas when it rebuilds its fonts, so send it ourselves.
+            let font_image = self.ctx.fonts(|f| f.image());
+            output.textures_delta.set.insert(
+                0,
+                (
+                    egui::TextureId::default(),
+                    egui::epaint::ImageDelta::full(font_image, egui::TextureOptions::LINEAR),
+                ),
+            );
+        }
+        if !self.chrome_applied {
+            // The root viewport's title and icon are applied once, during the first frame.
+            if let Some(root) = output.viewport_output.get(&egui::ViewportId::ROOT) {
+                if let Some(ref title) = root.builder.title {
+                    rl.set_window_title(rthread, title);
+                }
+                if let Some(ref icon) = root.builder.icon {
+                    paint::set_window_icon(rl, icon);
+                }
+            }
+            self.chrome_applied = true;
+        }
+        for command in output.viewport_output.values().flat_map(|v| &v.commands) {
+            match command {
+                egui::ViewportCommand::Close => self.close_requested = true,
+                egui::ViewportCommand::CancelClose => self.close_requested = false,
+                egui::ViewportCommand::Title(title) => rl.set_window_title(rthread, title),
+                egui::ViewportCommand::Icon(Some(icon)) => paint::set_window_icon(rl, icon),
+                egui::ViewportCommand::CursorGrab(grab) => {
+                    self.grab_requested = *grab != egui::viewport::CursorGrab::None
+                }
+                _ => {}
+            }
+        }
+        if self.cursor_grab {
+            // Horizontal scrubbing is how drag values are edited.
+            let scrubbing = self.ctx.dragged_id().is_some()
+                && output.platform_output.cursor_icon == egui::CursorIcon::ResizeHorizontal;
+            self.lock_cursor(rl, self.grab_requested || scrubbing);
+        }
+        self.repaint_delay = paint::repaint_delay(&output);
+        self.painter.tessellation_options = self.ctx.tessellation_options(|o| *o);
+        self.painter.max_texture_side = self.inopt.max_texture_size.unwrap_or(0);
+        self.changed =
+            has_input || self.repaint_delay.is_zero() || !output.textures_delta.is_empty();
+        let prepared = self.painter.predraw(output, rl, rthread)?;
         self.prs.replace(prepared);
+        if self.cached_rendering {
+            self.render_cache(rl, rthread, self.changed);
+        }
+        Ok(())
+    }
+
+    /// Run one iteration of a low-power main loop for apps that only show a gui, e.g. tools.
+    /// Input is gathered every iteration, but egui only runs when there was input, or it asked to be repainted by now
+    /// (see [RlEgui::needs_repaint] and [RlEgui::repaint_delay]). Its frame is only drawn (over the panel color)
+    /// when there was input, something changed, or the repaint is due.
+    /// Otherwise the window keeps showing the last frame, and this waits for a frame's time before polling input again,
+    /// so that input still wakes the gui within a frame. Raylib's target FPS is set to the monitor's refresh rate.
+    /// [egui::Context] is `Send + Sync`, so other threads (e.g. receiving async data) may call [egui::Context::request_repaint]
+    /// or [egui::Context::request_repaint_after] on a clone of `ctx`: egui notifies [RlEgui::needs_repaint] right away,
+    /// so the first iteration (at most a frame) after the repaint is due draws.
+    /// Returns whether a frame was drawn.
+    ///
+    /// ```no_run
+    /// # use egui_raylib::{DummyHandler, RlEgui};
+    /// let (mut rl, thread) = raylib::init().size(640, 480).title("Tool").build();
+    /// let mut gui = RlEgui::default();
+    /// let worker = gui.ctx.clone();
+    /// std::thread::spawn(move || {
+    ///     // ... receive some data, then wake the gui to show it.
+    ///     worker.request_repaint();
+    /// });
+    /// while !rl.window_should_close() {
+    ///     gui.run_event_driven(&mut rl, &thread, &mut DummyHandler, |ctx| {
+    ///         egui::CentralPanel::default().show(ctx, |ui| ui.label("Hello world!"));
+    ///     });
+    /// }
+    /// ```
+    /// # Panics
+    /// If a texture could not be uploaded, see [RlEgui::try_prepare_with].
+    pub fn run_event_driven<F, H>(
+        &mut self,
+        rl: &mut RaylibHandle,
+        rthread: &RaylibThread,
+        handler: &mut H,
+        run_ui: F,
+    ) -> bool
+    where
+        F: FnOnce(&egui::Context),
+        H: PlatformHandler,
+    {
+        let fps =
+            match raylib::window::get_monitor_refresh_rate(raylib::window::get_current_monitor()) {
+                // Unknown, e.g. on the web.
+                0 => 60,
+                rate => rate as u32,
+            };
+        // The rate only changes when the window moves to another monitor.
+        if self.target_fps != Some(fps) {
+            rl.set_target_fps(fps);
+            self.target_fps = Some(fps);
+        }
+        // Requests made while gathering (e.g. by a resize) are for this frame, like those made before.
+        let requested = self.take_repaint_request();
+        let raw_input = gather_input(&self.inopt, &mut self.instate, &self.ctx, rl);
+        let now = std::time::Instant::now();
+        let due = self.repaint_deadline.is_some_and(|d| now >= d);
+        // Without input or a repaint due, egui would only lay out the same gui again. Its first frame always runs.
+        let idle = raw_input.events.is_empty()
+            && !requested
+            && !self.needs_repaint()
+            && !due
+            && self.ctx.frame_nr() > 0;
+        if !idle {
+            if let Err(e) = self.try_prepare_gathered(rl, rthread, raw_input, run_ui, handler) {
+                panic!("egui-raylib: {e}");
+            }
+            // egui's delay is `Duration::MAX` if it doesn't need repainting at all, which overflows to `None`.
+            let next = now.checked_add(self.repaint_delay);
+            if self.changed || due {
+                self.repaint_deadline = next;
+                let fill = self.ctx.style().visuals.panel_fill.convert();
+                let mut d = rl.begin_drawing(rthread);
+                d.clear_background(fill);
+                self.draw(&mut d);
+                // Ending drawing waits for the rest of the frame, and polls input.
+                return true;
+            }
+            // A frame that isn't drawn mustn't postpone an earlier repaint.
+            self.repaint_deadline = match (self.repaint_deadline, next) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            self.prs = None;
+        }
+        std::thread::sleep(std::time::Duration::from_secs_f64(1.0 / fps as f64));
+        // Ending drawing would poll input, so it must be done here instead.
+        // SAFETY: `rl` and `rthread` show that the window is open, and that this is the thread it was opened on.
+        unsafe { raylib::ffi::PollInputEvents() };
+        false
+    }
+
+    /// Lock (or unlock) the cursor, if it isn't already.
+    fn lock_cursor(&mut self, rl: &mut RaylibHandle, lock: bool) {
+        if lock == self.cursor_locked {
+            return;
+        }
+        if lock {
+            rl.disable_cursor();
+        } else {
+            rl.enable_cursor();
+        }
+        self.cursor_locked = lock;
+    }
+
+    /// Render the prepared shapes into the cache, if they `changed` or the cache doesn't fit the screen.
+    fn render_cache(&mut self, rl: &mut RaylibHandle, rthread: &RaylibThread, changed: bool) {
+        let (width, height) = (rl.get_screen_width(), rl.get_screen_height());
+        let fits = self
+            .cache
+            .as_ref()
+            .is_some_and(|c| c.texture.width == width && c.texture.height == height);
+        if fits && !changed {
+            return;
+        }
+        if !fits {
+            match rl.load_render_texture(rthread, width as u32, height as u32) {
+                Ok(target) => self.cache = Some(target),
+                Err(e) => {
+                    eprintln!("egui-raylib: Failed to create render texture for cached rendering, drawing directly instead.\n\tdetail: {e}");
+                    self.cache = None;
+                    return;
+                }
+            }
+        }
+        let (Some(cache), Some(prs)) = (self.cache.as_mut(), self.prs.as_ref()) else {
+            return;
+        };
+        // The cache is drawn faded instead, so that fading doesn't need it redrawn.
+        let global_alpha = self.painter.global_alpha.take();
+        let mut d = rl.begin_texture_mode(rthread, cache);
+        d.clear_background(raylib::color::Color::BLANK);
+        self.painter
+            .paint_region(prs, &mut d, egui::Rect::EVERYTHING);
+        self.painter.global_alpha = global_alpha;
+    }
+
+    /// Run egui once without any input, and return the shapes of `run_ui`, e.g. to measure a layout or test it headless.
+    /// Nothing is uploaded to the GPU, so no window is needed; text shapes are returned even if the font atlas isn't uploaded yet.
+    /// Texture changes are kept, and uploaded with the next prepared frame. The platform output (cursor, clipboard, links) is ignored.
+    /// The frame is laid out in the last prepared frame's screen rect, and takes its place in egui's state, like any other.
+    pub fn tessellate_now<F>(&mut self, run_ui: F) -> Vec<egui::epaint::ClippedShape>
+    where
+        F: FnOnce(&egui::Context),
+    {
+        let viewport = egui::ViewportInfo {
+            native_pixels_per_point: self.instate.native_pixels_per_point(),
+            ..Default::default()
+        };
+        let raw_input = egui::RawInput {
+            viewports: std::iter::once((egui::ViewportId::ROOT, viewport)).collect(),
+            max_texture_side: Some(self.inopt.max_texture_side()),
+            ..Default::default()
+        };
+        let output = self.ctx.run(raw_input, run_ui);
+        self.deferred_textures.append(output.textures_delta);
+        output.shapes
+    }
+
+    /// Upload egui's font atlas ahead of the first frame, by running egui once without any gui.
+    /// Apps that show text right away can call this after creating the window, so that the first frames don't have to wait for it.
+    pub fn prewarm_fonts(
+        &mut self,
+        rl: &mut RaylibHandle,
+        rthread: &RaylibThread,
+    ) -> Result<(), error::EguiRaylibError> {
+        // Real input, so that the atlas is built for the same pixels per point as the first frame.
+        let raw_input = gather_input(&self.inopt, &mut self.instate, &self.ctx, rl);
+        let output = self.ctx.run(raw_input, |_| {});
+        self.painter.max_texture_side = self.inopt.max_texture_size.unwrap_or(0);
+        // Only the texture deltas are of interest, the (empty) shapes are dropped.
+        self.painter.predraw(output, rl, rthread).map(|_| ())
+    }
+
+    /// Same as [RlEgui::prepare], but uses the raylib thread stored at construction.
+    /// # Panics
+    /// If this struct was not created using [RlEgui::new_with_thread].
+    pub fn prepare_stored<F>(&mut self, rl: &mut RaylibHandle, run_ui: F)
+    where
+        F: FnOnce(&egui::Context),
+    {
+        self.prepare_stored_with(rl, run_ui, &mut DummyHandler);
+    }
+
+    /// Same as [RlEgui::prepare_with], but uses the raylib thread stored at construction.
+    /// # Panics
+    /// If this struct was not created using [RlEgui::new_with_thread].
+    pub fn prepare_stored_with<F, H>(&mut self, rl: &mut RaylibHandle, run_ui: F, handler: &mut H)
+    where
+        F: FnOnce(&egui::Context),
+        H: PlatformHandler,
+    {
+        let thread = self
+            .thread
+            .clone()
+            .expect("RlEgui should be created with `new_with_thread` to use the stored thread.");
+        self.prepare_with(rl, &thread, run_ui, handler);
+    }
+
+    /// The input options used to gather input.
+    pub fn input_options(&self) -> &InputOptions {
+        &self.inopt
+    }
+
+    /// Change the input options, which take effect from the next prepared frame.
+    ///
+    /// E.g. a docking layout may move [InputOptions::region] every frame; egui then lays out its screen to fill the region only,
+    /// and only gets pointer events inside it.
+    /// Egui's areas must be rectangular though. To leave the center of the window free (e.g. for a 3D view) while egui has panels around it,
+    /// keep the whole window as the region and only show side/top/bottom panels, without a `CentralPanel`.
+    /// Nothing is drawn where there are no panels, and the rect left over for the view is [egui::Context::available_rect]:
+    /// ```ignore
+    /// gui.prepare(&mut rl, &thread, |ctx| {
+    ///     egui::SidePanel::left("tools").show(ctx, |ui| { /* ... */ });
+    ///     egui::SidePanel::right("inspector").show(ctx, |ui| { /* ... */ });
+    ///     viewport = ctx.available_rect();
+    /// });
+    /// ```
+    /// Use [RlEgui::input_capture] to tell whether the pointer is over a panel.
+    pub fn input_options_mut(&mut self) -> &mut InputOptions {
+        &mut self.inopt
+    }
+
+    /// Whether egui asked for the window to be closed (using [egui::ViewportCommand::Close]).
+    /// Raylib won't close the window on its own, so check this alongside `window_should_close`:
+    /// ```ignore
+    /// while !rl.window_should_close() && !gui.close_requested() {
+    ///     // ...
+    /// }
+    /// ```
+    pub fn close_requested(&self) -> bool {
+        self.close_requested
+    }
+
+    /// Drop the font atlas and the images of egui's loaders, e.g. to recover GPU memory or after reloading assets.
+    ///
+    /// The font atlas is re-uploaded during the next [RlEgui::prepare].
+    /// Images loaded through egui's loaders are forgotten (using [egui::Context::forget_all_images]), so they are freed as usual, and loaded and sent again when next shown.
+    /// No handle is invalidated: textures from [egui::Context::load_texture] and [RlEgui::register_texture] are kept, as egui would never send the former again.
+    pub fn clear_textures(&mut self) {
+        self.reupload_font = self.painter.clear_font_texture();
+        self.ctx.forget_all_images();
+    }
+
+    /// Set egui's tessellation options.
+    ///
+    /// Most shapes are drawn directly using raylib's primitives and are unaffected by these.
+    /// Egui uses them for meshes it builds itself, as does the painter for filled paths and asymmetrically rounded rects.
+    /// `round_text_to_pixels` snaps glyphs to whole pixels, keeping text crisp while scrolling.
+    pub fn set_tessellation_options(&mut self, options: egui::epaint::TessellationOptions) {
+        self.ctx.tessellation_options_mut(|o| *o = options);
+    }
+
+    /// Keep a copy of the input sent to egui each frame, for [RlEgui::last_raw_input]. Off by default, since it costs a clone per frame.
+    pub fn set_keep_raw_input(&mut self, keep: bool) {
+        self.keep_raw_input = keep;
+        if !keep {
+            self.last_raw_input = None;
+        }
+    }
+
+    /// Mark where each layer [egui::layers::Order] begins in prepared frames, for [RlEgui::draw_orders], [RlEgui::draw_background] and [RlEgui::draw_foreground].
+    /// Off by default, since it costs an area per order each frame. Without it, all shapes count as [egui::layers::Order::Background].
+    ///
+    /// Enable it before the first frame, so that the markers are stacked below the app's areas.
+    pub fn set_split_layers(&mut self, split: bool) {
+        self.split_layers = split;
+    }
+
+    /// The input sent to egui in the last prepared frame, e.g. to print it while debugging input.
+    /// `None` unless enabled with [RlEgui::set_keep_raw_input] before that frame.
+    pub fn last_raw_input(&self) -> Option<&egui::RawInput> {
+        self.last_raw_input.as_ref()
+    }
+
+    /// In strict mode, unsupported shapes (paint callbacks without a [paint::TextureCallback] payload) panic instead of being skipped with a warning.
+    /// Useful during testing, to notice such shapes early. Off by default.
+    /// To handle them without panicking, draw with [RlEgui::draw_collecting] instead.
+    pub fn set_strict_mode(&mut self, strict: bool) {
+        self.painter.strict_mode = strict;
+    }
+
+    /// Set the 2D camera the gui is drawn through, i.e. when [RlEgui::draw] is called within `begin_mode2D`, so that it can live inside a pannable, zoomable scene.
+    /// Shapes are transformed by the camera anyway, but egui's clip rects are not, so without this the gui is clipped in the wrong places.
+    /// Input is still gathered in screen space though, so such a gui can't be interacted with properly unless the camera is the identity.
+    /// Rotated cameras clip to the bounding box of each rotated clip rect. Guis drawn outside `begin_mode2D` must not set a camera.
+    pub fn set_camera(&mut self, camera: Option<raylib::camera::Camera2D>) {
+        self.painter.camera = camera;
+    }
+
+    /// Let egui lock the cursor (with `disable_cursor`) when it asks for a cursor grab, and while a drag value is scrubbed,
+    /// so that values can be dragged indefinitely without the cursor hitting the edge of the screen.
+    /// Raylib can't confine the cursor to the window, so confining locks it too.
+    /// Off by default, so that games which lock the cursor themselves aren't disrupted. Turning it off unlocks the cursor if egui locked it.
+    pub fn set_cursor_grab(&mut self, rl: &mut RaylibHandle, grab: bool) {
+        self.cursor_grab = grab;
+        if !grab {
+            self.lock_cursor(rl, false);
+        }
+    }
+
+    /// Draw the gui at `render_pixels_per_point`, instead of the pixels per point input is gathered and laid out at
+    /// (i.e. [InputOptions::native_pixels_per_point] times egui's zoom factor). `None` draws at the latter.
+    ///
+    /// Drawing at fewer pixels per point makes the gui smaller, so draw it into a correspondingly smaller render texture and scale that up to the screen,
+    /// like a game's render-scale setting. Hit-testing still uses the input scale, so it stays accurate.
+    /// The upscaled gui is blurrier, text especially, since glyphs are rasterized for the input scale and then resampled twice.
+    pub fn set_render_pixels_per_point(&mut self, render_pixels_per_point: Option<f32>) {
+        self.painter.render_pixels_per_point = render_pixels_per_point;
+    }
+
+    /// End line segments flat, instead of rounding them off as egui does.
+    /// Saves drawing two circles per segment, at the cost of slightly different looking thick lines. Off by default.
+    pub fn set_butt_caps(&mut self, butt: bool) {
+        self.painter.butt_caps = butt;
+    }
+
+    /// Color to draw strokes colored by position (egui's uv color mode, e.g. for gradients) in, until those are supported.
+    /// Defaults to [raylib::color::Color::GRAY], which neither glares on dark themes nor vanishes on light ones;
+    /// a loud color (e.g. magenta) makes such strokes easy to spot instead.
+    pub fn set_uv_fallback_color(&mut self, color: raylib::color::Color) {
+        self.painter.uv_fallback_color = Some(color);
+    }
+
+    /// Fade the whole gui in or out, e.g. for splash screens and transitions, by multiplying the opacity of everything drawn by `alpha`.
+    /// This is on top of egui's own opacity (e.g. [egui::Ui::multiply_opacity]), so faded widgets stay relatively faded.
+    /// `alpha` is clamped to `0.0..=1.0`, and `1.0` (the default) draws the gui as is.
+    pub fn set_global_alpha(&mut self, alpha: f32) {
+        let alpha = alpha.clamp(0.0, 1.0);
+        self.painter.global_alpha = (alpha < 1.0).then_some(alpha);
+    }
+
+    /// Experimental: draw text with a raylib font (e.g. from `rl.load_font`) instead of egui's font atlas, or go back to the atlas with `None`.
+    ///
+    /// Each run of equally formatted text is drawn with a single `draw_text_ex` at egui's font size, which is cheaper than drawing every glyph from the atlas.
+    /// However egui still lays out text using its own fonts, so unless both fonts have the same metrics, text may overflow or fall short of its widget,
+    /// and the text cursor and selection won't line up with the glyphs. Glyphs missing from the raylib font (e.g. egui's icons) are not drawn.
+    /// Best suited to text-heavy guis with simple Latin text. Egui's atlas is used by default.
+    pub fn set_text_font(&mut self, font: Option<raylib::text::Font>) {
+        self.painter.text_font = font;
+    }
+
+    /// Render the gui into an internal render texture, which is only redrawn when egui repaints, there's new input, or the screen is resized.
+    /// Otherwise [RlEgui::draw] just draws that texture, which saves a lot of work for mostly static guis.
+    ///
+    /// This costs a screen-sized texture of GPU memory.
+    /// Translucent shapes are blended twice (into the texture, then onto the screen), so they may look slightly fainter.
+    /// Off by default.
+    pub fn set_cached_rendering(&mut self, cached: bool) {
+        self.cached_rendering = cached;
+        if !cached {
+            self.cache = None;
+        }
+    }
+
+    /// Draw the gui mirrored vertically, e.g. into a `RenderTexture2D` that is later drawn with a negative source height,
+    /// as raylib's render textures are stored upside down.
+    /// The gui is mirrored against the height of the screen it was prepared for, so the render texture should have the screen's size.
+    pub fn set_flip_y(&mut self, flip: bool) {
+        self.painter.flip_y = flip;
+    }
+
+    /// Tell the painter whether backface culling is enabled while the gui is drawn.
+    /// Culling is turned off to draw egui's meshes, and afterwards restored to this, as rlgl can't be asked what it was.
+    /// Raylib enables culling at start-up, so this is only needed if the app draws with it disabled (`rlDisableBackfaceCulling`).
+    pub fn set_backface_culling(&mut self, enabled: bool) {
+        self.painter.culling_disabled = !enabled;
+    }
+
+    /// Rely on the hardware's multisample anti-aliasing instead of egui's feathering.
+    ///
+    /// MSAA can only be requested before the window is created, with `raylib::init().msaa_4x()` (i.e. `FLAG_MSAA_4X_HINT`).
+    /// This checks that it was, and if so disables feathering so that edges aren't anti-aliased twice.
+    /// Returns `false` (and changes nothing) if MSAA was not requested.
+    pub fn enable_msaa(&mut self, rl: &RaylibHandle) -> bool {
+        if !rl.get_window_state().msaa() {
+            eprintln!("egui-raylib: MSAA was not enabled at window creation, use `raylib::init().msaa_4x()`. Keeping feathering.");
+            return false;
+        }
+        self.ctx.tessellation_options_mut(|o| o.feathering = false);
+        true
+    }
+
+    /// How long egui can wait before it must be repainted, as of the last prepared frame.
+    /// Apps that only redraw on input should still redraw once this elapses, so that the text cursor blinks and spinners spin.
+    /// [std::time::Duration::MAX] means nothing is animating. Repaints requested from other threads since the frame before show up here too.
+    pub fn repaint_delay(&self) -> std::time::Duration {
+        self.repaint_delay
+    }
+
+    /// Whether egui asked to be repainted by now, e.g. by an animation, or another thread calling [egui::Context::request_repaint]
+    /// (or [egui::Context::request_repaint_after]) on a clone of `ctx`. This is how such a thread wakes [RlEgui::run_event_driven].
+    /// Unlike [RlEgui::repaint_delay] this sees requests as soon as they're made, so an event-driven app may check it
+    /// while waiting for input, and wake up at once.
+    /// Cleared whenever a frame is prepared (and by every iteration of [RlEgui::run_event_driven]) once the repaint is due.
+    pub fn needs_repaint(&self) -> bool {
+        let now = std::time::Instant::now();
+        self.repaint_requested
+            .lock()
+            .is_ok_and(|requested| requested.is_some_and(|by| now >= by))
+    }
+
+    /// Clear the repaint request, if it is due. Returns whether it was.
+    fn take_repaint_request(&self) -> bool {
+        let now = std::time::Instant::now();
+        let Ok(mut requested) = self.repaint_requested.lock() else {
+            return false;
+        };
+        let due = requested.is_some_and(|by| now >= by);
+        if due {
+            *requested = None;
+        }
+        due
+    }
+
+    /// Take the output events (clicks, value changes, focus changes) of the last prepared frame.
+    /// These are also passed to the [PlatformHandler], but this lets e.g. test code check them without a custom handler:
+    /// ```ignore
+    /// gui.prepare(&mut rl, &thread, |ctx| { /* ... */ });
+    /// let saved = gui.drain_output_events().iter().any(|e| matches!(e,
+    ///     egui::output::OutputEvent::Clicked(info) if info.label.as_deref() == Some("Save")));
+    /// ```
+    /// Events are replaced on every prepare, so they must be drained after each one to see them all.
+    pub fn drain_output_events(&mut self) -> Vec<egui::output::OutputEvent> {
+        std::mem::take(&mut self.output_events)
+    }
+
+    /// Which modifier keys were held down in the last prepared frame, telling left and right keys apart.
+    pub fn modifier_keys(&self) -> input::ModifierKeys {
+        self.instate.modifier_keys()
+    }
+
+    /// What input egui consumed in the last prepared frame.
+    /// Game input should generally be ignored when it was captured by egui.
+    pub fn input_capture(&self) -> input::InputCapture {
+        input::InputCapture {
+            pointer: self.ctx.wants_pointer_input() || self.ctx.is_pointer_over_area(),
+            keyboard: self.ctx.wants_keyboard_input(),
+        }
+    }
+
+    /// Key colors of the current egui visuals, converted to raylib colors.
+    /// Query this every frame (or after changing themes) to keep raylib-drawn elements consistent with the gui.
+    pub fn accent_colors(&self) -> util::PaletteRl {
+        self.ctx.style().visuals.convert()
+    }
+
+    /// Set egui's zoom factor, i.e. how much larger than its native size the gui is drawn.
+    /// The zoom is clamped to [ZOOM_RANGE], and takes effect from the next prepared frame.
+    /// Pressing Ctrl (Cmd on macOS) and plus or minus zooms too.
+    ///
+    /// This crate stores no state of its own to persist the zoom with: egui keeps it in [egui::Options::zoom_factor],
+    /// which is part of [egui::Memory]. With egui's `persistence` feature on, an app that saves `ctx.memory(|m| m.clone())`
+    /// on exit and restores it with `ctx.memory_mut(|m| *m = saved)` gets its zoom back on the next prepared frame.
+    /// A restored zoom is not clamped again.
+    pub fn set_zoom(&self, zoom: f32) {
+        self.ctx
+            .set_zoom_factor(zoom.clamp(*ZOOM_RANGE.start(), *ZOOM_RANGE.end()));
+    }
+
+    /// Magnify the gui by one step, see [input::ZOOM_STEP].
+    pub fn zoom_in(&self) {
+        self.set_zoom(self.ctx.zoom_factor() * input::ZOOM_STEP);
+    }
+
+    /// Shrink the gui by one step, see [input::ZOOM_STEP].
+    pub fn zoom_out(&self) {
+        self.set_zoom(self.ctx.zoom_factor() / input::ZOOM_STEP);
+    }
+
+    /// Set egui's visuals, e.g. a customized theme. Same as calling [egui::Context::set_visuals] on `ctx`.
+    /// Takes effect from the next prepared frame, as does the palette returned by [RlEgui::accent_colors].
+    pub fn set_visuals(&self, visuals: egui::Visuals) {
+        self.ctx.set_visuals(visuals);
+    }
+
+    /// Switch to egui's default dark theme.
+    pub fn set_dark_theme(&self) {
+        self.set_visuals(egui::Visuals::dark());
+    }
+
+    /// Switch to egui's default light theme.
+    pub fn set_light_theme(&self) {
+        self.set_visuals(egui::Visuals::light());
+    }
+
+    /// Switch between the default dark and light themes, depending on whether the current visuals are dark.
+    pub fn toggle_theme(&self) {
+        if self.ctx.style().visuals.dark_mode {
+            self.set_light_theme();
+        } else {
+            self.set_dark_theme();
+        }
+    }
+
+    /// Register a raylib texture for use in egui, e.g. with [egui::Image].
+    /// The texture is owned by this struct until it is unregistered.
+    /// Images may also be rotated, and tinted:
+    /// ```ignore
+    /// let id = gui.register_texture(texture);
+    /// // ... later, inside the ui closure.
+    /// ui.add(egui::Image::new((id, egui::vec2(64.0, 64.0))).rotate(angle, egui::Vec2::splat(0.5)).tint(tint));
+    /// ```
+    pub fn register_texture(&mut self, texture: raylib::texture::Texture2D) -> egui::TextureId {
+        self.painter.register_texture(texture)
+    }
+
+    /// Register a raylib texture holding many images (e.g. sprites), so that egui can show regions of it.
+    /// Like [RlEgui::register_texture], the texture is owned by this struct until unregistered with the handle's [paint::AtlasHandle::id].
+    pub fn register_atlas(&mut self, texture: raylib::texture::Texture2D) -> paint::AtlasHandle {
+        self.painter.register_atlas(texture)
+    }
+
+    /// Unregister a texture registered using [RlEgui::register_texture], giving back ownership.
+    pub fn unregister_texture(&mut self, id: egui::TextureId) -> Option<raylib::texture::Texture2D> {
+        self.painter.unregister_texture(id)
+    }
+
+    /// Register a raylib texture that is owned elsewhere, e.g. the texture of a render target that is redrawn every frame,
+    /// for use in egui like [RlEgui::register_texture]. Only the texture's handle is kept, so nothing is copied or moved,
+    /// and the texture is never unloaded by this struct; updates to it show up in the gui as they are made.
+    /// ```ignore
+    /// let target = rl.load_render_texture(&thread, 256, 256)?;
+    /// // Safety: `target` outlives the registration.
+    /// let id = unsafe { gui.register_texture_ref(target.texture()) };
+    /// // ... draw into `target` and show it with `egui::Image`, then before dropping `target`:
+    /// gui.unregister_texture_ref(id);
+    /// ```
+    /// # Safety
+    /// The caller must keep the texture loaded for as long as it is registered,
+    /// and unregister it with [RlEgui::unregister_texture_ref] before unloading (i.e. dropping) it.
+    /// Otherwise egui draws with a deleted GPU texture, or another texture that reused its id.
+    pub unsafe fn register_texture_ref(
+        &mut self,
+        texture: &impl AsRef<raylib::ffi::Texture2D>,
+    ) -> egui::TextureId {
+        self.painter.register_texture_ref(texture)
+    }
+
+    /// Unregister a texture registered using [RlEgui::register_texture_ref], after which its owner may unload it.
+    /// Returns `false` if there was no such texture.
+    pub fn unregister_texture_ref(&mut self, id: egui::TextureId) -> bool {
+        self.painter.unregister_texture_ref(id)
+    }
+
+    /// Draw a registered texture as a nine-patch over `rect` (in points), e.g. to skin the frame of a panel.
+    /// `insets` are the sizes of the texture's borders in texture pixels, which stay unstretched.
+    /// Call this around [RlEgui::draw] to place the frame beneath or above the gui.
+    /// Returns `false` if `id` is not a known texture.
+    pub fn draw_nine_patch<D: RaylibDraw>(
+        &self,
+        d: &mut D,
+        id: egui::TextureId,
+        rect: egui::Rect,
+        insets: egui::Margin,
+        tint: raylib::color::Color,
+    ) -> bool {
+        let pxpp = self.ctx.pixels_per_point();
+        let rect: raylib::math::Rectangle = egui::Rect::from_min_max(
+            (rect.min.to_vec2() * pxpp).to_pos2(),
+            (rect.max.to_vec2() * pxpp).to_pos2(),
+        )
+        .convert();
+        self.painter
+            .draw_nine_patch(d, id, rect.into(), insets, tint)
+    }
+
+    /// Draw only the part of the previously prepared shapes that lies within `region` (in points).
+    /// Unlike [RlEgui::draw], the prepared shapes are kept, so different regions may be drawn at different times.
+    /// # Panics
+    /// If [RlEgui::prepare] was never called.
+    pub fn draw_region<D>(&self, d: &mut D, region: egui::Rect)
+    where
+        D: RaylibDraw + RaylibScissorModeExt,
+    {
+        let prepared_shapes = self
+            .prs
+            .as_ref()
+            .expect("GUI should be prepared before drawing. There are no prepared shapes now.");
+        self.painter.paint_region(prepared_shapes, d, region);
+    }
+
+    /// Draw only the previously prepared shapes on layers of the given [egui::layers::Order]s,
+    /// so that the app can draw between layers, e.g. a crosshair above windows but below tooltips:
+    /// draw `..Order::Tooltip`, then the crosshair, then `Order::Tooltip..`.
+    /// Like [RlEgui::draw_region] the prepared shapes are kept, and the cache of [RlEgui::set_cached_rendering] isn't used.
+    /// The orders are only told apart when enabled with [RlEgui::set_split_layers].
+    /// # Panics
+    /// If [RlEgui::prepare] was never called.
+    pub fn draw_orders<D>(&self, d: &mut D, orders: impl std::ops::RangeBounds<egui::layers::Order>)
+    where
+        D: RaylibDraw + RaylibScissorModeExt,
+    {
+        let prepared_shapes = self
+            .prs
+            .as_ref()
+            .expect("GUI should be prepared before drawing. There are no prepared shapes now.");
+        self.painter.paint_orders(prepared_shapes, d, orders);
+    }
+
+    /// Draw the previously prepared shapes on layers below `threshold`, before the app draws its own content
+    /// (e.g. a 3D scene), to then draw the rest above it with [RlEgui::draw_foreground]:
+    /// ```ignore
+    /// // Once, before the first frame.
+    /// gui.set_split_layers(true);
+    /// gui.prepare(&mut rl, &thread, |ctx| {
+    ///     // Panels are on the background layer. Give them no fill, or they cover the scene drawn above them.
+    ///     egui::SidePanel::left("tools").show(ctx, |ui| ui.label("Tools"));
+    ///     // Areas and windows choose their order, e.g. a HUD in front of the scene.
+    ///     egui::Area::new("hud".into()).order(Order::Foreground).show(ctx, |ui| ui.label("HUD"));
+    /// });
+    /// let mut d = rl.begin_drawing(&thread);
+    /// gui.draw_background(&mut d, Order::Middle);
+    /// // ... draw the scene ...
+    /// gui.draw_foreground(&mut d, Order::Middle);
+    /// ```
+    /// Windows are on the [egui::layers::Order::Middle] layer, so `Order::Middle` puts them in front, and `Order::Foreground` behind.
+    /// # Panics
+    /// If [RlEgui::prepare] was never called.
+    pub fn draw_background<D>(&self, d: &mut D, threshold: egui::layers::Order)
+    where
+        D: RaylibDraw + RaylibScissorModeExt,
+    {
+        self.draw_orders(d, ..threshold);
+    }
+
+    /// Draw the previously prepared shapes on layers from `threshold` on, after [RlEgui::draw_background] and the app's own content.
+    /// Like [RlEgui::draw], this takes the prepared shapes.
+    /// # Panics
+    /// If [RlEgui::prepare] was never called after the last draw.
+    pub fn draw_foreground<D>(&mut self, d: &mut D, threshold: egui::layers::Order)
+    where
+        D: RaylibDraw + RaylibScissorModeExt,
+    {
+        self.draw_orders(d, threshold..);
+        self.prs = None;
+    }
+
+    /// Draw the previously prepared shapes, and return those that can't be drawn, see [Painter::paint_collecting].
+    /// Like [RlEgui::draw_region], the cache of [RlEgui::set_cached_rendering] isn't used.
+    /// # Panics
+    /// If [RlEgui::prepare] was never called after the last draw.
+    pub fn draw_collecting<D>(&mut self, d: &mut D) -> Vec<egui::epaint::ClippedShape>
+    where
+        D: RaylibDraw + RaylibScissorModeExt,
+    {
+        let prepared_shapes = self
+            .prs
+            .take()
+            .expect("GUI should be prepared before drawing. There are no prepared shapes now.");
+        self.painter.paint_collecting(prepared_shapes, d)
     }
 
     /// Draw the previosly prepared shapes.
@@ -119,6 +1105,23 @@ impl RlEgui {
             .prs
             .take()
             .expect("GUI should be prepared before drawing. There are no prepared shapes now.");
+        // The cache only exists while cached rendering is on.
+        if let Some(cache) = self.cache.as_ref() {
+            // Render textures are stored upside down, hence the negative height.
+            let source = raylib::ffi::Rectangle {
+                x: 0.0,
+                y: 0.0,
+                width: cache.texture.width as f32,
+                height: -cache.texture.height as f32,
+            };
+            d.draw_texture_rec(
+                cache.texture(),
+                source,
+                raylib::math::Vector2::zero(),
+                raylib::color::Color::WHITE.alpha(self.painter.global_alpha.unwrap_or(1.0)),
+            );
+            return;
+        }
         self.painter.paint(prepared_shapes, d);
     }
 }
@@ -1,23 +1,40 @@
 //! A module to handle computing the full output, and painting it to screen.
 
+use egui::epaint::shape_transform::adjust_colors;
 use egui::epaint::tessellator::Path;
-use egui::epaint::{ClippedShape, ColorMode, Shape};
+use egui::epaint::{
+    ClippedShape, ColorMode, PathStroke, RectShape, Shape, TessellationOptions, Tessellator,
+    TextShape,
+};
+use egui::layers::Order;
 use egui::{
-    ahash::HashMap, epaint::ImageDelta, output::OutputEvent, Context, FullOutput, OpenUrl,
-    RawInput, TextureId,
+    ahash::{HashMap, HashSet},
+    epaint::ImageDelta,
+    output::OutputEvent,
+    Context, FullOutput, OpenUrl, RawInput, TextureId,
 };
-use egui::{Mesh, Rounding, Vec2};
+use egui::{Mesh, Rounding, Vec2, ViewportCommand};
+use raylib::camera::Camera2D;
 use raylib::color::Color;
-use raylib::drawing::RaylibScissorModeExt;
+use raylib::drawing::{RaylibScissorModeExt, RaylibTextureMode, RaylibTextureModeExt};
 use raylib::ffi::Rectangle;
 use raylib::math::Vector2;
+use raylib::text::Font;
 use raylib::RaylibThread;
 use raylib::{drawing::RaylibDraw, ffi::MouseCursor, RaylibHandle};
+use std::cell::Cell;
+use std::ops::{Range, RangeBounds};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 use raylib::texture::Image as rayImage;
-use raylib::texture::{RaylibTexture2D, Texture2D as rayTexture};
+use raylib::texture::{RaylibTexture2D, RenderTexture2D, Texture2D as rayTexture};
+use raylib::window::WindowState;
 
-use crate::util::ConvertRE;
+use crate::base::flip_rect_y;
+use crate::error::{log_error, EguiRaylibError};
+use crate::util::{egui_rect_to_scissor, rl_image_from_rgba, ConvertRE};
 
 /// Trait to handle egui's platform-specific output.
 pub trait PlatformHandler {
@@ -27,75 +44,367 @@ pub trait PlatformHandler {
     fn output_events(&mut self, vec: &[OutputEvent]);
 }
 
-fn change_mouse_cursor(rl: &mut RaylibHandle, icon: egui::CursorIcon) {
-    let raylib_icon = match icon {
-        egui::CursorIcon::Default => MouseCursor::MOUSE_CURSOR_DEFAULT,
-        egui::CursorIcon::None => {
-            rl.hide_cursor();
-            return;
+/// Resolve `url` against the absolute url `base`, as a browser resolves links.
+/// Urls with a scheme (e.g. `https:` or `mailto:`) are absolute, and returned unchanged.
+/// Others replace the part of `base` they're relative to, after which `.` and `..` path segments are removed.
+pub(crate) fn resolve_url(base: &str, url: &str) -> String {
+    let has_scheme = url.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    });
+    if has_scheme {
+        return url.to_owned();
+    }
+    // The base's fragment never carries over, and its query only does to fragments.
+    let base = base.split('#').next().unwrap_or(base);
+    let authority = base.find("://").map_or(0, |i| i + 3);
+    // A query may follow the authority directly, with an empty path.
+    let path_start = base[authority..]
+        .find(['/', '?'])
+        .map_or(base.len(), |i| authority + i);
+    let path_end = base.find('?').unwrap_or(base.len()).max(path_start);
+    if url.starts_with("//") {
+        return format!("{}{url}", &base[..authority.saturating_sub(2)]);
+    } else if url.is_empty() || url.starts_with('#') {
+        return format!("{base}{url}");
+    } else if url.starts_with('?') {
+        return format!("{}{url}", &base[..path_end]);
+    }
+    let path = if url.starts_with('/') {
+        url.to_owned()
+    } else {
+        let dir = &base[path_start..path_end];
+        let dir = &dir[..dir.rfind('/').map_or(0, |i| i + 1)];
+        format!("{}{url}", if dir.is_empty() { "/" } else { dir })
+    };
+    // The query and fragment of `url` are kept as they are.
+    let (path, tail) = path
+        .find(['?', '#'])
+        .map_or((path.as_str(), ""), |i| path.split_at(i));
+    let count = path.split('/').count();
+    let mut segments = Vec::new();
+    for (i, segment) in path.split('/').enumerate().skip(1) {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+        // A path ending in a dot segment names a directory.
+        if i == count - 1 && matches!(segment, "." | "..") {
+            segments.push("");
         }
+    }
+    format!("{}/{}{tail}", &base[..path_start], segments.join("/"))
+}
+
+/// Who hid the window's cursor, kept across frames by the caller of [full_output], e.g. one per [crate::RlEgui].
+#[derive(Debug, Default)]
+pub struct CursorState {
+    /// Whether the cursor was hidden by egui's cursor icon (as opposed to the app, or by locking it), so egui may show it again.
+    egui_hid: bool,
+    /// Whether egui hid the cursor with [ViewportCommand::CursorVisible], which holds regardless of the cursor icon.
+    invisible: bool,
+}
+
+/// Raylib's closest match for egui's cursor `icon`, or `None` to hide the cursor.
+/// Resize cursors always keep their direction: raylib has no cursors for single edges, so these share the double arrow
+/// of their axis, and column/row resizing (e.g. table or panel dividers) gets the horizontal/vertical one.
+pub(crate) fn raylib_cursor(icon: egui::CursorIcon) -> Option<MouseCursor> {
+    Some(match icon {
+        egui::CursorIcon::Default => MouseCursor::MOUSE_CURSOR_DEFAULT,
+        egui::CursorIcon::None => return None,
         egui::CursorIcon::ContextMenu => MouseCursor::MOUSE_CURSOR_ARROW,
         egui::CursorIcon::Help => MouseCursor::MOUSE_CURSOR_POINTING_HAND,
         egui::CursorIcon::PointingHand => MouseCursor::MOUSE_CURSOR_POINTING_HAND,
-        egui::CursorIcon::Crosshair => MouseCursor::MOUSE_CURSOR_CROSSHAIR,
+        egui::CursorIcon::Crosshair | egui::CursorIcon::Cell => MouseCursor::MOUSE_CURSOR_CROSSHAIR,
         egui::CursorIcon::Text => MouseCursor::MOUSE_CURSOR_IBEAM,
         egui::CursorIcon::VerticalText => MouseCursor::MOUSE_CURSOR_IBEAM,
         egui::CursorIcon::NoDrop => MouseCursor::MOUSE_CURSOR_NOT_ALLOWED,
         egui::CursorIcon::NotAllowed => MouseCursor::MOUSE_CURSOR_NOT_ALLOWED,
         egui::CursorIcon::Grab => MouseCursor::MOUSE_CURSOR_ARROW,
         egui::CursorIcon::Grabbing => MouseCursor::MOUSE_CURSOR_POINTING_HAND,
-        egui::CursorIcon::ResizeHorizontal => MouseCursor::MOUSE_CURSOR_RESIZE_EW,
-        egui::CursorIcon::ResizeNeSw => MouseCursor::MOUSE_CURSOR_RESIZE_NESW,
-        egui::CursorIcon::ResizeNwSe => MouseCursor::MOUSE_CURSOR_RESIZE_NWSE,
-        egui::CursorIcon::ResizeVertical => MouseCursor::MOUSE_CURSOR_RESIZE_NS,
-        egui::CursorIcon::ResizeEast => MouseCursor::MOUSE_CURSOR_RESIZE_EW,
-        egui::CursorIcon::ResizeSouthEast => MouseCursor::MOUSE_CURSOR_RESIZE_NWSE,
-        egui::CursorIcon::ResizeSouth => MouseCursor::MOUSE_CURSOR_RESIZE_NS,
-        egui::CursorIcon::ResizeSouthWest => MouseCursor::MOUSE_CURSOR_RESIZE_NESW,
-        egui::CursorIcon::ResizeWest => MouseCursor::MOUSE_CURSOR_RESIZE_EW,
-        egui::CursorIcon::ResizeNorthWest => MouseCursor::MOUSE_CURSOR_RESIZE_NWSE,
-        egui::CursorIcon::ResizeNorth => MouseCursor::MOUSE_CURSOR_RESIZE_NS,
-        egui::CursorIcon::ResizeNorthEast => MouseCursor::MOUSE_CURSOR_RESIZE_NESW,
-        egui::CursorIcon::ResizeColumn => MouseCursor::MOUSE_CURSOR_RESIZE_ALL,
-        egui::CursorIcon::ResizeRow => MouseCursor::MOUSE_CURSOR_RESIZE_ALL,
+        egui::CursorIcon::Move | egui::CursorIcon::AllScroll => {
+            MouseCursor::MOUSE_CURSOR_RESIZE_ALL
+        }
+        egui::CursorIcon::ResizeHorizontal
+        | egui::CursorIcon::ResizeEast
+        | egui::CursorIcon::ResizeWest
+        | egui::CursorIcon::ResizeColumn => MouseCursor::MOUSE_CURSOR_RESIZE_EW,
+        egui::CursorIcon::ResizeVertical
+        | egui::CursorIcon::ResizeNorth
+        | egui::CursorIcon::ResizeSouth
+        | egui::CursorIcon::ResizeRow => MouseCursor::MOUSE_CURSOR_RESIZE_NS,
+        egui::CursorIcon::ResizeNeSw
+        | egui::CursorIcon::ResizeNorthEast
+        | egui::CursorIcon::ResizeSouthWest => MouseCursor::MOUSE_CURSOR_RESIZE_NESW,
+        egui::CursorIcon::ResizeNwSe
+        | egui::CursorIcon::ResizeNorthWest
+        | egui::CursorIcon::ResizeSouthEast => MouseCursor::MOUSE_CURSOR_RESIZE_NWSE,
         _ => MouseCursor::MOUSE_CURSOR_DEFAULT,
+    })
+}
+
+fn change_mouse_cursor(rl: &mut RaylibHandle, cursor: &mut CursorState, icon: egui::CursorIcon) {
+    let Some(raylib_icon) = raylib_cursor(icon) else {
+        if !rl.is_cursor_hidden() {
+            rl.hide_cursor();
+            cursor.egui_hid = true;
+        }
+        return;
     };
-    if rl.is_cursor_hidden() {
+    if !cursor.invisible && std::mem::take(&mut cursor.egui_hid) {
         rl.show_cursor();
     }
     rl.set_mouse_cursor(raylib_icon);
 }
 
+/// Payload for an [egui::PaintCallback] that draws with raylib into a render texture of its own,
+/// which is then drawn into the callback's rect, clipped like any other shape.
+///
+/// Whatever state the callback sets up (e.g. depth testing and blending for a 3D viewport) stays isolated from egui's drawing.
+/// The texture is the size of the rect in pixels, and cleared to [Color::BLANK] before the callback is run.
+/// Callbacks run while the gui is prepared, outside of raylib's drawing mode, and only once per frame each.
+/// Their textures are pooled by size, so a callback whose rect keeps its size doesn't allocate one every frame.
+/// ```ignore
+/// let viewport = TextureCallback::new(move |_info, d| {
+///     d.clear_background(Color::SKYBLUE);
+///     let mut d = d.begin_mode3D(camera);
+///     d.draw_cube(Vector3::zero(), 1.0, 1.0, 1.0, Color::RED);
+/// });
+/// ui.painter().add(egui::PaintCallback { rect, callback: Arc::new(viewport) });
+/// ```
+pub struct TextureCallback(Box<DrawIntoTexture>);
+
+/// The drawing function of a [TextureCallback].
+type DrawIntoTexture =
+    dyn Fn(&egui::PaintCallbackInfo, &mut RaylibTextureMode<RaylibHandle>) + Send + Sync;
+
+impl TextureCallback {
+    /// Draw into the callback's texture with `draw`, which is told the rect (`viewport`) and clip rect in points,
+    /// the pixels per point, and the size of the texture (`screen_size_px`).
+    pub fn new(
+        draw: impl Fn(&egui::PaintCallbackInfo, &mut RaylibTextureMode<RaylibHandle>)
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Self(Box::new(draw))
+    }
+}
+
+/// A texture atlas registered with [RlEgui::register_atlas](crate::RlEgui::register_atlas), whose regions can be shown in egui, e.g. sprites:
+/// ```ignore
+/// let atlas = gui.register_atlas(texture);
+/// // ... later, inside the ui closure.
+/// let (id, uv) = atlas.sub(egui::Rect::from_min_size(egui::pos2(32.0, 0.0), egui::vec2(16.0, 16.0)));
+/// ui.add(egui::Image::new((id, egui::vec2(32.0, 32.0))).uv(uv));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasHandle {
+    pub(crate) id: TextureId,
+    /// Size of the texture in pixels.
+    pub(crate) size: Vec2,
+}
+
+impl AtlasHandle {
+    /// The id of the whole texture, e.g. to unregister it with [RlEgui::unregister_texture](crate::RlEgui::unregister_texture).
+    pub fn id(&self) -> TextureId {
+        self.id
+    }
+
+    /// Size of the texture in pixels.
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    /// The texture id and uv rect (as used by [egui::Image::uv]) of the region `rect` of the atlas, in texture pixels.
+    pub fn sub(&self, rect: egui::Rect) -> (TextureId, egui::Rect) {
+        let uv = egui::Rect::from_min_max(
+            (rect.min.to_vec2() / self.size).to_pos2(),
+            (rect.max.to_vec2() / self.size).to_pos2(),
+        );
+        (self.id, uv)
+    }
+}
+
+/// Identity of a paint callback's payload, under which its render texture is kept.
+fn callback_key(cb: &egui::PaintCallback) -> usize {
+    Arc::as_ptr(&cb.callback) as *const () as usize
+}
+
+/// Payload of the paint callbacks marking where each layer [Order] begins in egui's output.
+struct LayerMarker(Order);
+
+/// Paint a marker at the start of every layer [Order], so that [Painter::predraw] can tell the orders apart.
+///
+/// egui paints its layers back to front: first by [Order], then by how areas of the same order are stacked,
+/// and finally any layers that aren't areas (e.g. panels in the background).
+/// Each marker lives in an area that, when marking is enabled before the first frame, is registered before any of the app's areas,
+/// so it stays at the bottom of its order.
+/// Areas shown before the first prepared frame (e.g. in a shared `ctx`) may end up counted as the previous order.
+pub(crate) fn mark_layers(ctx: &Context) {
+    for order in Order::ALL {
+        let layer_id = egui::Area::new(egui::Id::new(("egui-raylib layer marker", order)))
+            .order(order)
+            .interactable(false)
+            .show(ctx, |ui| ui.layer_id())
+            .inner;
+        // Painting through the area's ui would paint nothing during its first (sizing) pass.
+        ctx.layer_painter(layer_id).add(egui::PaintCallback {
+            rect: egui::Rect::NOTHING,
+            callback: Arc::new(LayerMarker(order)),
+        });
+    }
+}
+
+/// Remove the markers of [mark_layers] from `shapes`, and return the remaining shapes with the order of each.
+pub(crate) fn split_orders(shapes: Vec<ClippedShape>) -> (Vec<ClippedShape>, Vec<Order>) {
+    let mut unmarked = Vec::with_capacity(shapes.len());
+    let mut orders = Vec::with_capacity(shapes.len());
+    // Shapes come out ordered back to front, so each marker ends the previous order.
+    let mut order = Order::Background;
+    for clipped_shape in shapes {
+        if let Shape::Callback(cb) = &clipped_shape.shape {
+            if let Some(LayerMarker(next)) = cb.callback.downcast_ref() {
+                order = *next;
+                continue;
+            }
+        }
+        unmarked.push(clipped_shape);
+        orders.push(order);
+    }
+    (unmarked, orders)
+}
+
 /// Obtain the full output of `ctx.run`, and process platform outputs.
 /// The handler's methods are invoked to handle url-open, or output events sent by egui.
+/// `cursor` tracks whether egui hid the cursor, and must be kept across frames of the same window.
 pub fn full_output<F, H>(
     rl: &mut RaylibHandle,
     raw_input: RawInput,
     ctx: &egui::Context,
     run_ui: F,
     handler: &mut H,
+    cursor: &mut CursorState,
 ) -> FullOutput
 where
     F: FnOnce(&Context),
     H: PlatformHandler,
 {
     let fout = ctx.run(raw_input, run_ui);
-    change_mouse_cursor(rl, fout.platform_output.cursor_icon);
+    change_mouse_cursor(rl, cursor, fout.platform_output.cursor_icon);
     if !fout.platform_output.copied_text.is_empty() {
-        if let Err(e) = rl.set_clipboard_text(&fout.platform_output.copied_text) {
-            eprintln!(
-                "egui-raylib: Failed to copy text \"{}\" to clipborad,\n\tdetail: {e}",
-                fout.platform_output.copied_text
-            );
+        if let Err(e) = write_clipboard(rl, &fout.platform_output.copied_text) {
+            let text = &fout.platform_output.copied_text;
+            log_error(&format!("Failed to copy text \"{text}\"."), &e);
         }
     }
     if let Some(ref s) = fout.platform_output.open_url {
         handler.open_url(s.to_owned())
     }
     handler.output_events(&fout.platform_output.events);
+    for command in fout.viewport_output.values().flat_map(|v| &v.commands) {
+        apply_viewport_command(rl, ctx, cursor, command);
+    }
     fout
 }
 
+/// Put `text` on the clipboard.
+pub fn write_clipboard(rl: &mut RaylibHandle, text: &str) -> Result<(), EguiRaylibError> {
+    rl.set_clipboard_text(text)
+        .map_err(|e| EguiRaylibError::Clipboard(e.to_string()))
+}
+
+/// How long egui can wait before it must be repainted, e.g. to blink the text cursor or animate a spinner.
+/// [Duration::MAX] means no repaint is needed until there is new input.
+pub fn repaint_delay(output: &FullOutput) -> Duration {
+    output
+        .viewport_output
+        .get(&egui::ViewportId::ROOT)
+        .map_or(Duration::MAX, |v| v.repaint_delay)
+}
+
+/// Apply those viewport commands that map directly onto raylib's window state.
+fn apply_viewport_command(
+    rl: &mut RaylibHandle,
+    ctx: &egui::Context,
+    cursor: &mut CursorState,
+    command: &ViewportCommand,
+) {
+    match *command {
+        ViewportCommand::Fullscreen(fullscreen) => {
+            if rl.is_window_fullscreen() != fullscreen {
+                rl.toggle_fullscreen();
+                // The screen size changes, so make sure the next frame is laid out for it.
+                ctx.request_repaint();
+            }
+        }
+        ViewportCommand::Decorations(decorated) => {
+            let undecorated = WindowState::default().set_window_undecorated(true);
+            if decorated {
+                rl.clear_window_state(undecorated);
+            } else {
+                rl.set_window_state(undecorated);
+            }
+        }
+        ViewportCommand::CursorVisible(visible) => {
+            if visible {
+                if std::mem::take(&mut cursor.invisible) {
+                    rl.show_cursor();
+                }
+            } else if !rl.is_cursor_hidden() {
+                rl.hide_cursor();
+                cursor.invisible = true;
+            }
+        }
+        ViewportCommand::CursorPosition(pos) => {
+            // Raylib resets the previous position too, so the jump doesn't show up as mouse motion next frame.
+            let ppp = ctx.pixels_per_point();
+            rl.set_mouse_position(Vector2::new(pos.x * ppp, pos.y * ppp));
+        }
+        ViewportCommand::WindowLevel(level) => {
+            let topmost = WindowState::default().set_window_topmost(true);
+            if level == egui::WindowLevel::AlwaysOnTop {
+                rl.set_window_state(topmost);
+            } else {
+                // Raylib can't keep a window below the others, so that is treated as normal.
+                rl.clear_window_state(topmost);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Set the window icon from egui's icon data.
+pub(crate) fn set_window_icon(rl: &mut RaylibHandle, icon: &egui::IconData) {
+    let size = [icon.width as usize, icon.height as usize];
+    match crate::util::try_image_from_rgba(size, &icon.rgba) {
+        Ok(image) => rl.set_window_icon(image),
+        Err(e) => eprintln!(
+            "egui-raylib: Icon data does not fit a {}x{} image, ignoring icon.\n\tdetail: {e}",
+            size[0], size[1]
+        ),
+    }
+}
+
+/// Create a raylib image from an egui color image, unmultiplying its alpha.
+/// [egui::Color32] is premultiplied RGBA, which is just RGBA for opaque pixels, so fully opaque images
+/// (most large ones, e.g. photos or backgrounds) have their buffer copied in one go instead of pixel by pixel.
+/// Finding out whether an image is opaque still reads every pixel, so this saves only the per-pixel conversion and writes;
+/// how much that gains has not been measured.
+pub(crate) fn color_image_to_rl(image: &egui::ColorImage) -> rayImage {
+    if image.pixels.iter().all(|p| p.is_opaque()) {
+        rl_image_from_rgba(image.size, image.as_raw())
+    } else {
+        let px = image.pixels.iter().map(|c| c.to_srgba_unmultiplied());
+        rimg_from_pixels(image.size, px)
+    }
+}
+
 /// Create a raylib image from pixels.
 /// Same as [crate::utils::rl_image_from_rgba], except uses slice of pixels instead of an iterator of bytes.
 /// # Safety
@@ -117,36 +426,208 @@ fn rimg_from_pixels(size: [usize; 2], pixels: impl Iterator<Item = [u8; 4]>) ->
     }
 }
 
+/// Whether a region ending at `max` (exclusive, in pixels) lies within a texture of `size`.
+/// Egui's font atlas grows as glyphs are added, so updates or glyphs beyond the font texture mean it's stale.
+pub(crate) fn fits_texture(size: [i32; 2], max: [usize; 2]) -> bool {
+    max[0] <= size[0].max(0) as usize && max[1] <= size[1].max(0) as usize
+}
+
+/// Set the wrap mode and filter of `tex` according to egui's `options`.
+fn apply_texture_options(tex: &rayTexture, options: egui::TextureOptions, rthread: &RaylibThread) {
+    let wrap_mode = match options.wrap_mode {
+        egui::TextureWrapMode::ClampToEdge => raylib::ffi::TextureWrap::TEXTURE_WRAP_CLAMP,
+        egui::TextureWrapMode::Repeat => raylib::ffi::TextureWrap::TEXTURE_WRAP_REPEAT,
+        egui::TextureWrapMode::MirroredRepeat => {
+            raylib::ffi::TextureWrap::TEXTURE_WRAP_MIRROR_REPEAT
+        }
+    };
+    tex.set_texture_wrap(rthread, wrap_mode);
+
+    // TODO: Figure out how to configure raylib to use different filters for minification and magnification.
+    let filter_mode = match options.magnification {
+        egui::TextureFilter::Nearest => raylib::ffi::TextureFilter::TEXTURE_FILTER_POINT,
+        egui::TextureFilter::Linear => raylib::ffi::TextureFilter::TEXTURE_FILTER_BILINEAR,
+    };
+    tex.set_texture_filter(rthread, filter_mode);
+}
+
 #[derive(Default)]
 /// Struct to manage [textures](raylib::texture::Texture2D) and handle drawing shapes.
 pub(crate) struct Painter {
     textures: HashMap<TextureId, rayTexture>,
+    /// Options last applied to each of egui's textures, so that partial updates only apply them when they change.
+    texture_options: HashMap<TextureId, egui::TextureOptions>,
     fonttex: Option<TextureId>,
+    /// Next free id for [TextureId::User] textures.
+    next_user_id: u64,
+    /// User textures in [Painter::textures] that are only borrowed, and must never be unloaded by the painter.
+    texture_refs: HashSet<TextureId>,
+    /// Egui's tessellation options, used wherever the painter builds meshes itself.
+    pub(crate) tessellation_options: TessellationOptions,
+    /// Largest texture side the GPU supports, zero if unknown, in which case no upload is refused.
+    pub(crate) max_texture_side: usize,
+    /// Textures egui freed in the last frame, to be removed before the next one.
+    pending_free: Vec<TextureId>,
+    /// Panic on unsupported shapes, instead of skipping them.
+    pub(crate) strict_mode: bool,
+    /// Whether a warning about unsupported shapes was already printed.
+    warned_unsupported: Cell<bool>,
+    /// Whether the font texture was found to be smaller than egui's atlas, which must then be uploaded whole again.
+    font_stale: Cell<bool>,
+    /// The render textures [TextureCallback]s drew into for the prepared frame, by [callback_key].
+    callback_targets: HashMap<usize, RenderTexture2D>,
+    /// Mirror everything vertically, for render textures that are drawn flipped.
+    pub(crate) flip_y: bool,
+    /// Whether backface culling is off while the gui is drawn, so that the painter leaves it off.
+    /// rlgl can't be asked, and raylib turns it on at start-up, hence the default.
+    pub(crate) culling_disabled: bool,
+    /// Draw text with this raylib font, instead of egui's glyphs.
+    pub(crate) text_font: Option<Font>,
+    /// End line segments flat, instead of rounding them off.
+    pub(crate) butt_caps: bool,
+    /// The 2D camera the gui is drawn through, if any.
+    pub(crate) camera: Option<Camera2D>,
+    /// Pixels per point to draw at, if different from those used for input and layout.
+    pub(crate) render_pixels_per_point: Option<f32>,
+    /// Multiply the opacity of everything drawn by this, if set.
+    pub(crate) global_alpha: Option<f32>,
+    /// Color of strokes colored by position (uv), which aren't supported yet. [Color::GRAY] if unset.
+    pub(crate) uv_fallback_color: Option<Color>,
+}
+
+/// Position (in pixels) of a glyph placed `offset` points away from the text's `origin` (in pixels).
+/// If `round` is set, the position is snapped to whole pixels, so that text stays crisp and doesn't shimmer while scrolling.
+pub(crate) fn glyph_position(origin: Vector2, offset: Vec2, pxpp: f32, round: bool) -> Vector2 {
+    let pos = Vector2::new(origin.x + offset.x * pxpp, origin.y + offset.y * pxpp);
+    if round {
+        Vector2::new(pos.x.round(), pos.y.round())
+    } else {
+        pos
+    }
+}
+
+/// Bounding box on screen of `rect` in world space, as seen through `camera`. Both are in pixels.
+/// Scissor rects are always in screen space, so this is what clips a gui drawn within `begin_mode2D` correctly.
+pub(crate) fn camera_clip_rect(rect: egui::Rect, camera: &Camera2D) -> egui::Rect {
+    let (sin, cos) = camera.rotation.to_radians().sin_cos();
+    let corners = [
+        rect.left_top(),
+        rect.right_top(),
+        rect.left_bottom(),
+        rect.right_bottom(),
+    ]
+    .map(|p| {
+        let x = (p.x - camera.target.x) * camera.zoom;
+        let y = (p.y - camera.target.y) * camera.zoom;
+        egui::pos2(
+            x * cos - y * sin + camera.offset.x,
+            x * sin + y * cos + camera.offset.y,
+        )
+    });
+    egui::Rect::from_points(&corners)
+}
+
+/// Whether `rs` is filled with a flat color, rather than with its texture.
+///
+/// egui fills plain rects with its default texture (the font atlas) at `uv == Rect::ZERO`, where the atlas has a white texel,
+/// so sampling it would only tint that texel with the fill color. Drawing the color directly gives the same result, without a texture.
+/// Any other texture is sampled even at zero uv, since it needn't be white there.
+pub(crate) fn is_flat_fill(rs: &RectShape) -> bool {
+    rs.fill_texture_id == TextureId::default() && rs.uv == egui::Rect::ZERO
+}
+
+/// Order texture uploads so that whole images are uploaded before any partial updates,
+/// which need their base texture to exist. The order is otherwise preserved.
+pub(crate) fn ordered_texture_sets(
+    mut set: Vec<(TextureId, ImageDelta)>,
+) -> Vec<(TextureId, ImageDelta)> {
+    set.sort_by_key(|(_, delta)| delta.pos.is_some());
+    set
 }
 
-fn color_mode_to_color(c: &ColorMode) -> Color {
+/// The color to draw `stroke` with. All strokes are resolved here, whether they're plain [egui::Stroke]s
+/// (circles, ellipses, rects), which convert losslessly, or [PathStroke]s (paths, lines, beziers).
+/// Strokes colored by position (uv) aren't supported yet, and are drawn in `uv_fallback` instead.
+pub(crate) fn stroke_color(stroke: &PathStroke, uv_fallback: Color) -> Color {
+    color_mode_to_color(&stroke.color, uv_fallback)
+}
+
+/// Multiply the opacity of `shape` by `alpha`, on top of whatever opacity egui gave it.
+pub(crate) fn fade_shape(shape: &mut Shape, alpha: f32) {
+    match shape {
+        Shape::Vec(shapes) => shapes.iter_mut().for_each(|s| fade_shape(s, alpha)),
+        // Glyphs are drawn in their sections' colors, which `adjust_colors` leaves alone.
+        Shape::Text(ts) => ts.opacity_factor *= alpha,
+        shape => adjust_colors(shape, &|c| *c = c.gamma_multiply(alpha)),
+    }
+}
+
+/// Whether a warning about strokes colored by position (uv) was already printed.
+static WARNED_UV_STROKE: AtomicBool = AtomicBool::new(false);
+
+fn color_mode_to_color(c: &ColorMode, uv_fallback: Color) -> Color {
     match c {
         ColorMode::Solid(c) => c.convert(),
         ColorMode::UV(_) => {
-            eprintln!("egui-raylib: UV color mode for paths and lines is not yet implemented! Falling back to WHITE.");
-            Color::WHITE
+            if !WARNED_UV_STROKE.swap(true, Ordering::Relaxed) {
+                eprintln!("egui-raylib: UV color mode for paths and lines is not yet implemented! Falling back to {uv_fallback:?}.");
+            }
+            uv_fallback
+        }
+    }
+}
+
+impl Drop for Painter {
+    fn drop(&mut self) {
+        // Borrowed textures belong to their owners, so they must outlive the painter.
+        for id in &self.texture_refs {
+            if let Some(texture) = self.textures.remove(id) {
+                texture.make_weak();
+            }
         }
     }
 }
 
 impl Painter {
+    /// Width (in points) of the anti-aliasing feather for meshes, zero if feathering is disabled.
+    fn feathering(&self, pxpp: f32) -> f32 {
+        if self.tessellation_options.feathering {
+            self.tessellation_options.feathering_size_in_pixels / pxpp
+        } else {
+            0.0
+        }
+    }
+
+    /// Color of strokes colored by position (uv), see [Painter::uv_fallback_color].
+    fn uv_fallback(&self) -> Color {
+        self.uv_fallback_color.unwrap_or(Color::GRAY)
+    }
+
+    /// Create a tessellator to build meshes for shapes that raylib can't draw directly.
+    fn tessellator(&self, pxpp: f32) -> Tessellator {
+        let font_tex_size = self
+            .fonttex
+            .and_then(|t| self.textures.get(&t))
+            .map_or([1, 1], |t| [t.width as usize, t.height as usize]);
+        Tessellator::new(pxpp, self.tessellation_options, font_tex_size, Vec::new())
+    }
+
     fn process_image_delta(
         &mut self,
         mapid: TextureId,
         delta: &ImageDelta,
         rthread: &RaylibThread,
         rl: &mut RaylibHandle,
-    ) {
-        let mut img = match &delta.image {
-            egui::ImageData::Color(c) => {
-                let px = c.pixels.iter().map(|c| c.to_srgba_unmultiplied());
-                rimg_from_pixels(c.size, px)
-            }
+    ) -> Result<(), EguiRaylibError> {
+        let [w, h] = delta.image.size();
+        if self.max_texture_side != 0 && delta.pos.is_none() && w.max(h) > self.max_texture_side {
+            return Err(EguiRaylibError::TextureUpload(format!(
+                "image of size {w}x{h} exceeds the maximum texture size {}",
+                self.max_texture_side
+            )));
+        }
+        let img = match &delta.image {
+            egui::ImageData::Color(c) => color_image_to_rl(c),
             egui::ImageData::Font(fontimg) => {
                 let px = fontimg
                     .srgba_pixels(None)
@@ -157,55 +638,48 @@ impl Painter {
         };
         let tex = match delta.pos {
             Some(pos) => {
-                // See if this section of code can be better.
-                /* --------------------- */
-                let tex = self
-                    .textures
-                    .get_mut(&mapid)
-                    .expect("ImageDelta updates should be accompanied by valid TextureId.");
-                let mut old_img = tex
-                    .load_image()
-                    .expect("You should be able to retrieve image from texture.");
-                let size = delta.image.size();
-                for x in 0..size[0] {
-                    for y in 0..size[1] {
-                        old_img.draw_pixel(
-                            (x + pos[0]) as i32,
-                            (y + pos[1]) as i32,
-                            img.get_color(x as i32, y as i32),
-                        )
+                let Some(tex) = self.textures.get(&mapid) else {
+                    eprintln!("egui-raylib: Partial update for unknown texture {mapid:?}, ignoring.");
+                    return Ok(());
+                };
+                if !fits_texture([tex.width, tex.height], [pos[0] + w, pos[1] + h]) {
+                    if self.fonttex == Some(mapid) {
+                        // The atlas grew, so it's uploaded whole before the next frame.
+                        self.font_stale.set(true);
+                    } else {
+                        eprintln!("egui-raylib: Partial update beyond the bounds of texture {mapid:?}, ignoring.");
                     }
+                    return Ok(());
+                }
+                // Only the changed region is uploaded, into the existing texture. Both are RGBA.
+                let region = Rectangle {
+                    x: pos[0] as f32,
+                    y: pos[1] as f32,
+                    width: w as f32,
+                    height: h as f32,
+                };
+                // SAFETY: `img` was built from the delta's pixels, so `img.data` holds `region.width * region.height` pixels,
+                // as R8G8B8A8 like every texture egui uploads, and the region lies within the texture (checked above).
+                unsafe { raylib::ffi::UpdateTextureRec(**tex, region, img.data) };
+                // Options rarely change, and setting them costs GL calls on the font atlas' hot path.
+                if self.texture_options.get(&mapid) != Some(&delta.options) {
+                    apply_texture_options(tex, delta.options, rthread);
+                    self.texture_options.insert(mapid, delta.options);
                 }
-                /* -------------------- */
-                rl.load_texture_from_image(rthread, &old_img).expect(
-                    "Image data should easily be sent to GPU. Texture could not be created.",
-                )
+                return Ok(());
             }
             None => rl
                 .load_texture_from_image(rthread, &img)
-                .expect("Image data should easily be sent to GPU. Texture could not be created."),
+                .map_err(|e| EguiRaylibError::TextureUpload(e.to_string()))?,
         };
-
-        let wrap_mode = match delta.options.wrap_mode {
-            egui::TextureWrapMode::ClampToEdge => raylib::ffi::TextureWrap::TEXTURE_WRAP_CLAMP,
-            egui::TextureWrapMode::Repeat => raylib::ffi::TextureWrap::TEXTURE_WRAP_REPEAT,
-            egui::TextureWrapMode::MirroredRepeat => {
-                raylib::ffi::TextureWrap::TEXTURE_WRAP_MIRROR_REPEAT
-            }
-        };
-        tex.set_texture_wrap(rthread, wrap_mode);
-
-        // TODO: Figure out how to configure raylib to use different filters for minification and magnification.
-        let filter_mode = match delta.options.magnification {
-            egui::TextureFilter::Nearest => raylib::ffi::TextureFilter::TEXTURE_FILTER_POINT,
-            egui::TextureFilter::Linear => raylib::ffi::TextureFilter::TEXTURE_FILTER_BILINEAR,
-        };
-        tex.set_texture_filter(rthread, filter_mode);
+        apply_texture_options(&tex, delta.options, rthread);
+        self.texture_options.insert(mapid, delta.options);
 
         self.textures.insert(mapid, tex); // If there was anything here before, it would be dropped.
+        Ok(())
     }
 
-    fn paint_shape(&self, pxpp: f32, shape: Shape, d: &mut impl RaylibDraw) {
+    fn paint_shape(&self, pxpp: f32, shape: &Shape, d: &mut impl RaylibDraw) {
         match shape {
 		    egui::Shape::Noop => { /* Do nothing */ },
 		    egui::Shape::Vec(v) => {
@@ -221,7 +695,7 @@ impl Painter {
 		    	let r1 = (c.radius + c.stroke.width) * pxpp;
 
 		    	// First draw stroke, then draw the real circle concentric to it.
-		    	d.draw_circle(center_x, center_y, r1, c.stroke.color.convert());
+		    	d.draw_circle(center_x, center_y, r1, stroke_color(&c.stroke.into(), self.uv_fallback()));
 		    	d.draw_circle(center_x, center_y, r2, c.fill.convert());
 		    },
 		    egui::Shape::Ellipse(es) => {
@@ -232,35 +706,36 @@ impl Painter {
 		    	let axes1 = es.radius + Vec2::new(es.stroke.width, es.stroke.width);
 		    	let axes2 = es.radius;
 
-		    	d.draw_ellipse(center_x, center_y, axes1.x, axes1.y, es.stroke.color.convert());
+		    	d.draw_ellipse(center_x, center_y, axes1.x, axes1.y, stroke_color(&es.stroke.into(), self.uv_fallback()));
 		    	d.draw_ellipse(center_x, center_y, axes2.x, axes2.y, es.fill.convert());
 		    },
 		    egui::Shape::LineSegment { points, stroke } => {
 		    	let start_pos = points[0].convert().scale_by(pxpp);
 		    	let end_pos = points[1].convert().scale_by(pxpp);
 		    	let thick = stroke.width * pxpp;
-		    	d.draw_line_ex(start_pos, end_pos, thick, color_mode_to_color(&stroke.color))
+		    	let color = stroke_color(stroke, self.uv_fallback());
+		    	d.draw_line_ex(start_pos, end_pos, thick, color);
+		    	if !self.butt_caps {
+		    		// Round the ends off, like egui does.
+		    		d.draw_circle_v(start_pos, thick / 2.0, color);
+		    		d.draw_circle_v(end_pos, thick / 2.0, color);
+		    	}
 		    },
 
 		    egui::Shape::Path(ps) => {
                 if ps.closed {
-                    let mut out = Mesh::default();
-                    let mut p = Path::default();
-                    let fill = ps.fill.convert();
-                    p.add_line_loop(&ps.points);
-                    p.fill(0.2, ps.fill, &mut out);
-                    for verts in out.indices.chunks_exact(3) {
-                        let p0 = out.vertices[verts[0] as usize].pos.convert().scale_by(pxpp);
-                        let p1 = out.vertices[verts[1] as usize].pos.convert().scale_by(pxpp);
-                        let p2 = out.vertices[verts[2] as usize].pos.convert().scale_by(pxpp);
-                        d.draw_triangle(p0, p1, p2, fill);
-                    }
-                } else {
+                    // The feathered edge fades out through vertex colors, so the fill is drawn as a mesh.
+                    self.paint_filled_curve(pxpp, &ps.points, ps.fill);
+                }
+                if ps.stroke.width > 0.0 {
+                    // The outline of a closed path loops back to its first point, over the fill.
+                    let segments = if ps.closed { ps.points.len() } else { ps.points.len().saturating_sub(1) };
                     let lines = ps.points.iter()
-                        .zip(ps.points.iter().skip(1))
+                        .zip(ps.points.iter().cycle().skip(1))
+                        .take(segments)
                         .map(|(a,b)| (a.convert().scale_by(pxpp), b.convert().scale_by(pxpp)));
                     let thick = ps.stroke.width * pxpp;
-                    let color = color_mode_to_color(&ps.stroke.color);
+                    let color = stroke_color(&ps.stroke, self.uv_fallback());
 
                     for (start_pos, end_pos) in lines {
                         d.draw_line_ex(start_pos, end_pos, thick, color)
@@ -284,27 +759,36 @@ impl Painter {
                     height: rrect.height + 2.0 * swidth
                 };
                 let fill_color = rs.fill.convert();
-                let stroke_color = rs.stroke.color.convert();
+                let stroke_color = stroke_color(&rs.stroke.into(), self.uv_fallback());
 
                 if rs.rounding == Rounding::ZERO {
                     d.draw_rectangle_rec(rrect2, stroke_color);
-                    if rs.uv == egui::Rect::ZERO {
-                        // No texture here.
+                    if is_flat_fill(rs) {
                         d.draw_rectangle_rec(rrect, fill_color);
                     } else {
                         // Draw textured rectangle.
                         if let Some(texture) = self.textures.get(&rs.fill_texture_id) {
+                            // The uv rect may address a sub-rect of the texture, e.g. of an atlas (see `AtlasHandle`).
                             let source_rec = Rectangle {
                                 x: rs.uv.min.x * texture.width as f32,
-                                y: rs.uv.max.y * texture.height as f32,
-                                width: rs.uv.width(),
-                                height: rs.uv.height()
+                                y: rs.uv.min.y * texture.height as f32,
+                                width: rs.uv.width() * texture.width as f32,
+                                height: rs.uv.height() * texture.height as f32
                             };
                             d.draw_texture_pro(texture, source_rec, rrect, Vector2::zero(), 0.0, fill_color)
                         } else {
+                            // Usually means a texture delta was dropped, or the texture was freed (or unregistered) too early.
+                            if cfg!(debug_assertions) {
+                                eprintln!("egui-raylib: Rect refers to unknown texture {:?}, drawing a flat fill instead.", rs.fill_texture_id);
+                            }
                             d.draw_rectangle_rec(rrect, fill_color)
                         }
                     }
+                } else if rs.rounding.nw != rs.rounding.ne || rs.rounding.ne != rs.rounding.sw || rs.rounding.sw != rs.rounding.se {
+                    // Raylib has a single roundness for all corners, so let egui build the exact geometry.
+                    let mut mesh = Mesh::default();
+                    self.tessellator(pxpp).tessellate_rect(rs, &mut mesh);
+                    self.paint_mesh(pxpp, &mesh);
                 } else {
                     // Can't draw textures on rounded rectangles.
                     // Raylib roundedness is the ratio between the radius and the smallest dimension.
@@ -315,17 +799,28 @@ impl Painter {
             },
 
 		    egui::Shape::Text(ts) => {
-                // TODO: Implement drawing text.
                 let origin = Vector2::new(ts.pos.x, ts.pos.y).scale_by(pxpp);
+                if let Some(font) = &self.text_font {
+                    self.paint_text_with_font(font, pxpp, origin, ts, d);
+                    return;
+                }
                 let font_texture = self.fonttex.and_then(|t| self.textures.get(&t)).expect("Font texture should have been sent as an ImageDelta by now..");
 
+                let font_size = [font_texture.width, font_texture.height];
+
                 for row in ts.galley.rows.iter() {
                     for g in row.glyphs.iter() {
+                        if !fits_texture(font_size, [g.uv_rect.max[0] as usize, g.uv_rect.max[1] as usize]) {
+                            // Laid out against a larger atlas than was uploaded; drawing it would show some other part of the atlas.
+                            self.font_stale.set(true);
+                            continue;
+                        }
                         let color = ts.override_text_color.unwrap_or_else(|| ts.galley.job.sections[g.section_index as usize].format.color);
-                        let tint = color.convert();
+                        let tint = color.gamma_multiply(ts.opacity_factor).convert();
+                        let pos = glyph_position(origin, g.pos.to_vec2() + g.uv_rect.offset, pxpp, self.tessellation_options.round_text_to_pixels);
                         let dst_rect = Rectangle {
-                            x: origin.x + (g.pos.x + g.uv_rect.offset.x) * pxpp,
-                            y: origin.y + (g.pos.y + g.uv_rect.offset.y) * pxpp,
+                            x: pos.x,
+                            y: pos.y,
                             width: g.uv_rect.size.x * pxpp,
                             height: g.uv_rect.size.y * pxpp
                         };
@@ -347,8 +842,11 @@ impl Painter {
 		    		qbez.points[1].convert().scale_by(pxpp),
 		    		qbez.points[2].convert().scale_by(pxpp)
 		    	];
+		    	if qbez.closed && qbez.fill != egui::Color32::TRANSPARENT {
+		    		self.paint_filled_curve(pxpp, &qbez.flatten(None), qbez.fill);
+		    	}
 		    	let thick = qbez.stroke.width * pxpp;
-		    	d.draw_spline_bezier_quadratic(points.as_slice(), thick, qbez.fill.convert())
+		    	d.draw_spline_bezier_quadratic(points.as_slice(), thick, stroke_color(&qbez.stroke, self.uv_fallback()))
 		    },
 		    egui::Shape::CubicBezier(cbez) => {
 		    	let points: [Vector2; 4] = [
@@ -357,30 +855,342 @@ impl Painter {
 		    		cbez.points[2].convert().scale_by(pxpp),
 		    		cbez.points[3].convert().scale_by(pxpp)
 		    	];
+		    	if cbez.closed && cbez.fill != egui::Color32::TRANSPARENT {
+		    		self.paint_filled_curve(pxpp, &cbez.flatten(None), cbez.fill);
+		    	}
 		    	let thick = cbez.stroke.width * pxpp;
-		    	d.draw_spline_bezier_cubic(points.as_slice(), thick, cbez.fill.convert());
+		    	d.draw_spline_bezier_cubic(points.as_slice(), thick, stroke_color(&cbez.stroke, self.uv_fallback()));
 		    },
-		    egui::Shape::Mesh(_) => unimplemented!("Haven't implemented drawing arbitrary meshes as there is no immediately obvious way of doing it using raylib."),
-		    egui::Shape::Callback(_) => unimplemented!("Implement support for PaintCallbacks."),
+		    egui::Shape::Mesh(m) => {
+                // Images (possibly rotated), color pickers and the like are all sent as meshes.
+                self.paint_mesh(pxpp, m);
+            },
+		    egui::Shape::Callback(cb) => {
+                if cb.callback.is::<TextureCallback>() {
+                    // Nothing was rendered for empty rects, or if the render texture couldn't be created.
+                    if let Some(target) = self.callback_targets.get(&callback_key(cb)) {
+                        // Render textures are stored upside down, hence the negative height.
+                        let source = Rectangle {
+                            x: 0.0,
+                            y: 0.0,
+                            width: target.texture.width as f32,
+                            height: -target.texture.height as f32,
+                        };
+                        let rect = Rectangle {
+                            x: cb.rect.min.x * pxpp,
+                            y: cb.rect.min.y * pxpp,
+                            width: cb.rect.width() * pxpp,
+                            height: cb.rect.height() * pxpp,
+                        };
+                        d.draw_texture_pro(target.texture(), source, rect, Vector2::zero(), 0.0, Color::WHITE);
+                    }
+                    return;
+                }
+                if self.strict_mode {
+                    panic!("{}", EguiRaylibError::UnsupportedShape("Paint callbacks"));
+                }
+                if !self.warned_unsupported.replace(true) {
+                    eprintln!("egui-raylib: Paint callbacks are only supported with a `TextureCallback` payload, others will not be drawn.");
+                }
+                if cfg!(debug_assertions) {
+                    // Make the missing content obvious during development.
+                    let rect = Rectangle {
+                        x: cb.rect.min.x * pxpp,
+                        y: cb.rect.min.y * pxpp,
+                        width: cb.rect.width() * pxpp,
+                        height: cb.rect.height() * pxpp,
+                    };
+                    d.draw_rectangle_rec(rect, Color::MAGENTA);
+                }
+            },
 		}
     }
 
+    /// Draw text with a raylib font, one run of equally formatted glyphs at a time, starting at `origin` (in pixels).
+    /// Each run starts where egui placed its first glyph, but is laid out by raylib, so it only roughly matches egui's metrics.
+    fn paint_text_with_font(
+        &self,
+        font: &Font,
+        pxpp: f32,
+        origin: Vector2,
+        ts: &TextShape,
+        d: &mut impl RaylibDraw,
+    ) {
+        for row in ts.galley.rows.iter() {
+            for run in row
+                .glyphs
+                .chunk_by(|a, b| a.section_index == b.section_index)
+            {
+                let format = &ts.galley.job.sections[run[0].section_index as usize].format;
+                let color = ts
+                    .override_text_color
+                    .unwrap_or(format.color)
+                    .gamma_multiply(ts.opacity_factor);
+                let text: String = run.iter().map(|g| g.chr).collect();
+                let offset = Vec2::new(run[0].pos.x, row.rect.min.y);
+                let pos = glyph_position(
+                    origin,
+                    offset,
+                    pxpp,
+                    self.tessellation_options.round_text_to_pixels,
+                );
+                d.draw_text_ex(
+                    font,
+                    &text,
+                    pos,
+                    format.font_id.size * pxpp,
+                    0.0,
+                    color.convert(),
+                );
+            }
+        }
+    }
+
+    /// Fill the area enclosed by `points` (in points), e.g. of a closed path or flattened bezier.
+    fn paint_filled_curve(&self, pxpp: f32, points: &[egui::Pos2], fill: egui::Color32) {
+        let mut out = Mesh::default();
+        let mut p = Path::default();
+        p.add_line_loop(points);
+        p.fill(self.feathering(pxpp), fill, &mut out);
+        self.paint_mesh(pxpp, &out);
+    }
+
+    /// Draw an arbitrary egui mesh using rlgl's immediate mode, so that every vertex carries its own color and uv.
+    /// egui expects the texel color to be multiplied by the vertex color (this is how glyphs and images are tinted),
+    /// which is exactly what raylib's default shader does.
+    /// Meshes referring to an unknown texture are drawn with raylib's default white texture, i.e. with vertex colors alone.
+    /// Must only be called while drawing.
+    fn paint_mesh(&self, pxpp: f32, mesh: &Mesh) {
+        use raylib::ffi;
+        let texture_id = match self.textures.get(&mesh.texture_id) {
+            Some(texture) => texture.id,
+            None => unsafe { ffi::rlGetTextureIdDefault() },
+        };
+        // egui only ever builds meshes of whole triangles; a stray partial one is dropped rather than left dangling.
+        debug_assert!(mesh.indices.len().is_multiple_of(3));
+        let indices = &mesh.indices[..mesh.indices.len() - mesh.indices.len() % 3];
+        // SAFETY: this is only called while drawing (see above), so rlgl is initialised and a batch is open.
+        // `texture_id` is either raylib's default texture or one held by `self.textures`, which isn't unloaded while `self` is borrowed.
+        // Every `rlBegin` gets a multiple of 3 vertices and is closed by `rlEnd`, and `rlCheckRenderBatchLimit` flushes
+        // the batch before a chunk that wouldn't fit, so large meshes never overflow rlgl's vertex buffer.
+        // Vertex lookups are bounds-checked, so a bad index panics instead of reading out of bounds.
+        unsafe {
+            // egui doesn't care about winding order, so culling must be off while the batch containing this mesh is drawn.
+            ffi::rlDrawRenderBatchActive();
+            ffi::rlDisableBackfaceCulling();
+            // Emit whole triangles per batch, so that a flush never splits one.
+            for triangles in indices.chunks(3 * 256) {
+                ffi::rlCheckRenderBatchLimit(triangles.len() as i32);
+                ffi::rlSetTexture(texture_id);
+                ffi::rlBegin(ffi::RL_TRIANGLES as i32);
+                for &index in triangles {
+                    let v = &mesh.vertices[index as usize];
+                    let c = v.color.to_srgba_unmultiplied();
+                    ffi::rlColor4ub(c[0], c[1], c[2], c[3]);
+                    ffi::rlTexCoord2f(v.uv.x, v.uv.y);
+                    ffi::rlVertex2f(v.pos.x * pxpp, v.pos.y * pxpp);
+                }
+                ffi::rlEnd();
+                ffi::rlSetTexture(0);
+            }
+            ffi::rlDrawRenderBatchActive();
+            // Culling stays off while flipped, see `paint_shapes`.
+            if !self.flip_y && !self.culling_disabled {
+                ffi::rlEnableBackfaceCulling();
+            }
+        }
+    }
+
+    /// Draw the texture `id` as a nine-patch stretched over `rect` (in pixels).
+    /// `insets` are the sizes of the borders in texture pixels; these are not stretched.
+    /// Returns `false` if there's no such texture.
+    pub fn draw_nine_patch(
+        &self,
+        d: &mut impl RaylibDraw,
+        id: TextureId,
+        rect: Rectangle,
+        insets: egui::Margin,
+        tint: Color,
+    ) -> bool {
+        let Some(texture) = self.textures.get(&id) else {
+            return false;
+        };
+        let info = raylib::ffi::NPatchInfo {
+            source: Rectangle {
+                x: 0.0,
+                y: 0.0,
+                width: texture.width as f32,
+                height: texture.height as f32,
+            },
+            left: insets.left as i32,
+            top: insets.top as i32,
+            right: insets.right as i32,
+            bottom: insets.bottom as i32,
+            layout: raylib::ffi::NPatchLayout::NPATCH_NINE_PATCH as i32,
+        };
+        d.draw_texture_n_patch(texture, info, rect, Vector2::zero(), 0.0, tint);
+        true
+    }
+
+    /// Drop the font atlas texture, which egui only sends again when asked to.
+    /// Other textures are kept, since egui does not re-send them. Returns `true` if there was a font texture.
+    pub fn clear_font_texture(&mut self) -> bool {
+        let Some(id) = self.fonttex.take() else {
+            return false;
+        };
+        self.textures.remove(&id);
+        self.texture_options.remove(&id);
+        true
+    }
+
+    /// Whether the font texture was found stale since this was last called, in which case egui's atlas must be uploaded whole again.
+    pub(crate) fn take_font_stale(&self) -> bool {
+        self.font_stale.take()
+    }
+
+    /// Take ownership of a raylib texture, and return the [TextureId] with which egui can refer to it.
+    pub fn register_texture(&mut self, texture: rayTexture) -> TextureId {
+        let id = TextureId::User(self.next_user_id);
+        self.next_user_id += 1;
+        self.textures.insert(id, texture);
+        id
+    }
+
+    /// Take ownership of a raylib texture used as an atlas, whose regions are addressed through the returned handle.
+    pub fn register_atlas(&mut self, texture: rayTexture) -> AtlasHandle {
+        let size = Vec2::new(texture.width as f32, texture.height as f32);
+        AtlasHandle {
+            id: self.register_texture(texture),
+            size,
+        }
+    }
+
+    /// Let egui refer to a raylib texture owned elsewhere, without taking ownership.
+    /// # Safety
+    /// The texture must stay loaded until it is unregistered with [Painter::unregister_texture_ref].
+    pub unsafe fn register_texture_ref(
+        &mut self,
+        texture: &impl AsRef<raylib::ffi::Texture2D>,
+    ) -> TextureId {
+        let id = self.register_texture(rayTexture::from_raw(*texture.as_ref()));
+        self.texture_refs.insert(id);
+        id
+    }
+
+    /// Remove a texture previously registered with [Painter::register_texture], and return it.
+    /// Borrowed textures aren't returned, but are unregistered all the same.
+    pub fn unregister_texture(&mut self, id: TextureId) -> Option<rayTexture> {
+        if self.unregister_texture_ref(id) {
+            return None;
+        }
+        self.textures.remove(&id)
+    }
+
+    /// Remove a texture previously registered with [Painter::register_texture_ref], leaving it loaded.
+    /// Returns `false` if there was no such texture.
+    pub fn unregister_texture_ref(&mut self, id: TextureId) -> bool {
+        if !self.texture_refs.remove(&id) {
+            return false;
+        }
+        if let Some(texture) = self.textures.remove(&id) {
+            // Unlike the texture, its weak handle doesn't unload it when dropped.
+            texture.make_weak();
+        }
+        true
+    }
+
     /// Perform pre-paint steps dealing with loading and freeing textures, then generate shapes.
+    /// Fails if a texture could not be uploaded, in which case the remaining textures are not uploaded either.
     pub fn predraw(
         &mut self,
         output: FullOutput,
         rl: &mut RaylibHandle,
         rthread: &RaylibThread,
-    ) -> PreparedShapes {
-        for (id, delta) in output.textures_delta.set {
-            self.process_image_delta(id, &delta, rthread, rl)
+    ) -> Result<PreparedShapes, EguiRaylibError> {
+        // Most frames change no textures at all, so there's nothing to do then.
+        if !self.pending_free.is_empty() || !output.textures_delta.is_empty() {
+            // egui frees textures after painting, so the previous frame's frees are only applied now.
+            for id in std::mem::take(&mut self.pending_free) {
+                // User textures are owned by the user, and must be unregistered explicitly.
+                if let TextureId::User(_) = id {
+                    eprintln!("egui-raylib: egui asked to free user texture {id:?}, ignoring.");
+                    continue;
+                }
+                self.textures.remove(&id);
+                self.texture_options.remove(&id);
+            }
+            for (id, delta) in ordered_texture_sets(output.textures_delta.set) {
+                self.process_image_delta(id, &delta, rthread, rl)?;
+            }
+            self.pending_free = output.textures_delta.free;
         }
-        for id in output.textures_delta.free {
-            self.textures.remove(&id);
+        let (shapes, orders) = split_orders(output.shapes);
+        let pxpp = self
+            .render_pixels_per_point
+            .unwrap_or(output.pixels_per_point);
+        self.render_callbacks(&shapes, pxpp, rl, rthread);
+        Ok(PreparedShapes {
+            shapes,
+            orders,
+            pxpp,
+            screen_size: Vec2::new(rl.get_screen_width() as f32, rl.get_screen_height() as f32),
+            uv_fallback: self.uv_fallback(),
+        })
+    }
+
+    /// Run the [TextureCallback]s among `shapes`, each drawing into a render texture for [Painter::paint_shape] to draw.
+    fn render_callbacks(
+        &mut self,
+        shapes: &[ClippedShape],
+        pxpp: f32,
+        rl: &mut RaylibHandle,
+        rthread: &RaylibThread,
+    ) {
+        // The last frame's textures are reused where the sizes match, and the rest are unloaded at the end.
+        let mut pool: HashMap<(u32, u32), Vec<RenderTexture2D>> = HashMap::default();
+        for (_, target) in self.callback_targets.drain() {
+            let size = (target.texture.width as u32, target.texture.height as u32);
+            pool.entry(size).or_default().push(target);
         }
-        PreparedShapes {
-            shapes: output.shapes,
-            pxpp: output.pixels_per_point,
+        for clipped_shape in shapes {
+            let Shape::Callback(cb) = &clipped_shape.shape else {
+                continue;
+            };
+            let Some(callback) = cb.callback.downcast_ref::<TextureCallback>() else {
+                continue;
+            };
+            let key = callback_key(cb);
+            if self.callback_targets.contains_key(&key) {
+                continue;
+            }
+            let size = cb.rect.size() * pxpp;
+            // Negative sizes (of collapsed rects) saturate to zero.
+            let (width, height) = (size.x.round() as u32, size.y.round() as u32);
+            if width == 0 || height == 0 {
+                continue;
+            }
+            let mut target = match pool.get_mut(&(width, height)).and_then(Vec::pop) {
+                Some(target) => target,
+                None => match rl.load_render_texture(rthread, width, height) {
+                    Ok(target) => target,
+                    Err(e) => {
+                        eprintln!("egui-raylib: Failed to create render texture for paint callback, skipping it.\n\tdetail: {e}");
+                        continue;
+                    }
+                },
+            };
+            let info = egui::PaintCallbackInfo {
+                viewport: cb.rect,
+                clip_rect: clipped_shape.clip_rect,
+                pixels_per_point: pxpp,
+                screen_size_px: [width, height],
+            };
+            {
+                let mut d = rl.begin_texture_mode(rthread, &mut target);
+                d.clear_background(Color::BLANK);
+                (callback.0)(&info, &mut d);
+            }
+            self.callback_targets.insert(key, target);
         }
     }
 
@@ -392,21 +1202,183 @@ impl Painter {
         d: &mut D,
     ) where
         D: RaylibDraw + RaylibScissorModeExt,
+    {
+        self.paint_region(&prs, d, egui::Rect::EVERYTHING);
+    }
+
+    /// Same as [Painter::paint], but shapes that can't be drawn are returned instead, for the app to handle itself,
+    /// e.g. to draw a placeholder or log them: paint callbacks other than [TextureCallback]s, and textured shapes
+    /// (or text) whose texture is unknown. These are neither drawn with a fallback, nor do they panic in strict mode.
+    /// Nested [Shape::Vec]s are split up, so only their failed parts are returned.
+    pub fn paint_collecting<D>(&self, prs: PreparedShapes, d: &mut D) -> Vec<ClippedShape>
+    where
+        D: RaylibDraw + RaylibScissorModeExt,
+    {
+        let mut failed = Vec::new();
+        self.paint_shapes(
+            &prs,
+            0..prs.len(),
+            d,
+            egui::Rect::EVERYTHING,
+            Some(&mut failed),
+        );
+        failed
+    }
+
+    /// Whether [Painter::paint_shape] can draw `shape` as intended, not counting nested shapes.
+    fn can_paint(&self, shape: &Shape) -> bool {
+        match shape {
+            Shape::Rect(rs) => is_flat_fill(rs) || self.textures.contains_key(&rs.fill_texture_id),
+            Shape::Mesh(mesh) => self.textures.contains_key(&mesh.texture_id),
+            Shape::Text(_) => {
+                self.text_font.is_some()
+                    || self.fonttex.is_some_and(|t| self.textures.contains_key(&t))
+            }
+            Shape::Callback(cb) => cb.callback.is::<TextureCallback>(),
+            _ => true,
+        }
+    }
+
+    /// Draw `shape`, faded by the global alpha if set.
+    fn paint_faded(&self, pxpp: f32, shape: &Shape, d: &mut impl RaylibDraw) {
+        if let Some(alpha) = self.global_alpha {
+            let mut shape = shape.clone();
+            fade_shape(&mut shape, alpha);
+            self.paint_shape(pxpp, &shape, d);
+        } else {
+            self.paint_shape(pxpp, shape, d);
+        }
+    }
+
+    /// Draw those parts of `shape` that [Painter::can_paint], and add the rest to `failed`.
+    fn paint_or_collect(
+        &self,
+        pxpp: f32,
+        clip_rect: egui::Rect,
+        shape: &Shape,
+        d: &mut impl RaylibDraw,
+        failed: &mut Vec<ClippedShape>,
+    ) {
+        match shape {
+            Shape::Vec(shapes) => {
+                for shape in shapes {
+                    self.paint_or_collect(pxpp, clip_rect, shape, d, failed);
+                }
+            }
+            shape if self.can_paint(shape) => self.paint_faded(pxpp, shape, d),
+            shape => failed.push(ClippedShape {
+                clip_rect,
+                shape: shape.clone(),
+            }),
+        }
+    }
+
+    /// Draw only those prepared shapes whose layer [Order] lies in `orders`, e.g. `..Order::Foreground`.
+    /// Painting the rest (`Order::Foreground..`) later puts anything drawn in between above the former and below the latter.
+    pub fn paint_orders<D>(&self, prs: &PreparedShapes, d: &mut D, orders: impl RangeBounds<Order>)
+    where
+        D: RaylibDraw + RaylibScissorModeExt,
+    {
+        self.paint_shapes(prs, prs.range_of(orders), d, egui::Rect::EVERYTHING, None);
+    }
+
+    /// Draw only those prepared shapes whose clip rect intersects `region` (in points), clipped to `region`.
+    /// The same shapes may be painted several times, e.g. different regions at different times or into different targets.
+    pub fn paint_region<D>(&self, prs: &PreparedShapes, d: &mut D, region: egui::Rect)
+    where
+        D: RaylibDraw + RaylibScissorModeExt,
+    {
+        self.paint_shapes(prs, 0..prs.len(), d, region, None);
+    }
+
+    /// The scissor rect (x, y, width and height in pixels) that clips to `clip_rect` (in points) on a screen `screen_size` pixels large,
+    /// or `None` if nothing within it is visible.
+    pub(crate) fn scissor(
+        &self,
+        clip_rect: egui::Rect,
+        pxpp: f32,
+        screen_size: Vec2,
+    ) -> Option<(i32, i32, i32, i32)> {
+        // Scissor rects aren't affected by the transform, so they're mirrored separately.
+        let clip_rect = if self.flip_y {
+            flip_rect_y(clip_rect, screen_size.y / pxpp)
+        } else {
+            clip_rect
+        };
+        let bounds = (screen_size.x as i32, screen_size.y as i32);
+        match &self.camera {
+            // Through a camera any part of the gui may be on screen, so it's clipped where the camera puts it.
+            Some(camera) => {
+                let clip_px = egui::Rect::from_min_max(
+                    (clip_rect.min.to_vec2() * pxpp).to_pos2(),
+                    (clip_rect.max.to_vec2() * pxpp).to_pos2(),
+                );
+                egui_rect_to_scissor(camera_clip_rect(clip_px, camera), 1.0, bounds)
+            }
+            None => egui_rect_to_scissor(clip_rect, pxpp, bounds),
+        }
+    }
+
+    /// Draw the prepared shapes at indices `range`, clipped to `region`.
+    /// Shapes that can't be drawn are added to `failed` if given, see [Painter::paint_collecting].
+    pub(crate) fn paint_shapes<D>(
+        &self,
+        prs: &PreparedShapes,
+        range: Range<usize>,
+        d: &mut D,
+        region: egui::Rect,
+        mut failed: Option<&mut Vec<ClippedShape>>,
+    ) where
+        D: RaylibDraw + RaylibScissorModeExt,
     {
         let pxpp = prs.pxpp;
-        let shapes = prs.shapes;
-        // Hereafter everything uses points, instead of pixels.
 
-        for clipped_shape in shapes {
-            let cx = (clipped_shape.clip_rect.min.x * pxpp) as i32;
-            let cy = (clipped_shape.clip_rect.min.y * pxpp) as i32;
-            let cw = (clipped_shape.clip_rect.width() * pxpp) as i32;
-            let ch = (clipped_shape.clip_rect.height() * pxpp) as i32;
+        if self.flip_y {
+            // Mirroring reverses the winding of every triangle, so culling must be off too.
+            unsafe {
+                raylib::ffi::rlDrawRenderBatchActive();
+                raylib::ffi::rlDisableBackfaceCulling();
+                raylib::ffi::rlPushMatrix();
+                raylib::ffi::rlTranslatef(0.0, prs.screen_size.y, 0.0);
+                raylib::ffi::rlScalef(1.0, -1.0, 1.0);
+            }
+        }
+
+        // Entering scissor mode flushes raylib's batch, so consecutive shapes with the same clip rect share one.
+        for run in prs.shapes[range].chunk_by(|a, b| a.clip_rect == b.clip_rect) {
+            let clip_rect = run[0].clip_rect.intersect(region);
+            let scissor = self.scissor(clip_rect, pxpp, prs.screen_size);
+            // Collapsed or inverted clip rects (hidden widgets, mid-animation panels) can't contain anything,
+            // and shapes clipped entirely off-screen (e.g. scrolled out of view) are never visible.
+            let Some((cx, cy, cw, ch)) = scissor else {
+                continue;
+            };
             {
                 let mut d = d.begin_scissor_mode(cx, cy, cw, ch);
-                self.paint_shape(pxpp, clipped_shape.shape, &mut d);
+                for clipped_shape in run {
+                    match failed.as_deref_mut() {
+                        Some(failed) => self.paint_or_collect(
+                            pxpp,
+                            clipped_shape.clip_rect,
+                            &clipped_shape.shape,
+                            &mut d,
+                            failed,
+                        ),
+                        None => self.paint_faded(pxpp, &clipped_shape.shape, &mut d),
+                    }
+                }
             } // Scissor mode ends here on drop.
         }
+
+        if self.flip_y {
+            unsafe {
+                raylib::ffi::rlDrawRenderBatchActive();
+                raylib::ffi::rlPopMatrix();
+                if !self.culling_disabled {
+                    raylib::ffi::rlEnableBackfaceCulling();
+                }
+            }
+        }
     }
 }
 
@@ -414,6 +1386,251 @@ impl Painter {
 pub struct PreparedShapes {
     /// All clipped shapes obtained from full-output.
     shapes: Vec<ClippedShape>,
+    /// The layer order of each shape, ascending.
+    orders: Vec<Order>,
     /// Pixels from point obtained from full-output.
     pxpp: f32,
+    /// Size of the screen in pixels, when the shapes were prepared.
+    screen_size: Vec2,
+    /// Color of strokes colored by position (uv), see [Painter::uv_fallback_color].
+    uv_fallback: Color,
+}
+
+impl PreparedShapes {
+    /// Number of (clipped) shapes, counting nested [Shape::Vec]s as one.
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    /// Whether there are no shapes to draw.
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    /// Pixels per point the shapes are drawn at.
+    pub fn pixels_per_point(&self) -> f32 {
+        self.pxpp
+    }
+
+    /// The layer [Order] of each shape, in painting order (hence ascending).
+    pub fn orders(&self) -> &[Order] {
+        &self.orders
+    }
+
+    /// Indices of the shapes whose order lies in `orders`. These are contiguous, as orders ascend.
+    pub(crate) fn range_of(&self, orders: impl RangeBounds<Order>) -> Range<usize> {
+        let start = self
+            .orders
+            .iter()
+            .position(|o| orders.contains(o))
+            .unwrap_or(self.orders.len());
+        let count = self.orders[start..]
+            .iter()
+            .take_while(|o| orders.contains(o))
+            .count();
+        start..start + count
+    }
+}
+
+#[cfg(feature = "svg")]
+impl PreparedShapes {
+    /// Describe the prepared shapes as an SVG document in points, e.g. for documentation or to diff frames in tests without a GPU.
+    ///
+    /// Circles, ellipses, rects, paths, lines and beziers become the matching SVG elements, clipped like on screen.
+    /// Text becomes the bounding box of each galley, titled with its text, and meshes (images, color pickers) their bounding box.
+    pub fn to_svg(&self) -> String {
+        let size = self.screen_size / self.pxpp;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+            size.x, size.y
+        );
+        for (i, run) in self
+            .shapes
+            .chunk_by(|a, b| a.clip_rect == b.clip_rect)
+            .enumerate()
+        {
+            let clip = run[0]
+                .clip_rect
+                .intersect(egui::Rect::from_min_size(egui::Pos2::ZERO, size));
+            svg.push_str(&format!(
+                "<clipPath id=\"clip{i}\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/></clipPath>\n",
+                clip.min.x,
+                clip.min.y,
+                clip.width().max(0.0),
+                clip.height().max(0.0)
+            ));
+            svg.push_str(&format!("<g clip-path=\"url(#clip{i})\">\n"));
+            for clipped_shape in run {
+                write_svg_shape(&mut svg, &clipped_shape.shape, self.uv_fallback);
+            }
+            svg.push_str("</g>\n");
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// SVG attributes painting `attr` (`fill` or `stroke`) with `color`.
+#[cfg(feature = "svg")]
+fn svg_paint(attr: &str, color: egui::Color32) -> String {
+    if color == egui::Color32::TRANSPARENT {
+        return format!("{attr}=\"none\"");
+    }
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    format!(
+        "{attr}=\"#{r:02x}{g:02x}{b:02x}\" {attr}-opacity=\"{}\"",
+        a as f32 / 255.0
+    )
+}
+
+/// SVG attributes for `stroke`. Like [stroke_color], strokes colored by position (uv) get `uv_fallback`.
+#[cfg(feature = "svg")]
+fn svg_stroke(stroke: &PathStroke, uv_fallback: Color) -> String {
+    if stroke.width <= 0.0 {
+        return "stroke=\"none\"".to_owned();
+    }
+    let color = match stroke.color {
+        ColorMode::Solid(c) => c,
+        ColorMode::UV(_) => egui::Color32::from_rgba_unmultiplied(
+            uv_fallback.r,
+            uv_fallback.g,
+            uv_fallback.b,
+            uv_fallback.a,
+        ),
+    };
+    format!(
+        "{} stroke-width=\"{}\"",
+        svg_paint("stroke", color),
+        stroke.width
+    )
+}
+
+/// Append `shape` (in points) to `svg` as SVG elements, see [PreparedShapes::to_svg].
+#[cfg(feature = "svg")]
+pub(crate) fn write_svg_shape(svg: &mut String, shape: &Shape, uv_fallback: Color) {
+    let points = |points: &[egui::Pos2]| {
+        points
+            .iter()
+            .map(|p| format!("{},{}", p.x, p.y))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let bounds = |class: &str, rect: egui::Rect, color: egui::Color32| {
+        format!(
+            "<rect class=\"{class}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" {}",
+            rect.min.x,
+            rect.min.y,
+            rect.width(),
+            rect.height(),
+            svg_stroke(&egui::Stroke::new(1.0, color).into(), uv_fallback)
+        )
+    };
+    let element = match shape {
+        Shape::Noop | Shape::Callback(_) => return,
+        Shape::Vec(v) => {
+            for shape in v {
+                write_svg_shape(svg, shape, uv_fallback);
+            }
+            return;
+        }
+        Shape::Circle(c) => format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" {} {}/>",
+            c.center.x,
+            c.center.y,
+            c.radius,
+            svg_paint("fill", c.fill),
+            svg_stroke(&c.stroke.into(), uv_fallback)
+        ),
+        Shape::Ellipse(e) => format!(
+            "<ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\" {} {}/>",
+            e.center.x,
+            e.center.y,
+            e.radius.x,
+            e.radius.y,
+            svg_paint("fill", e.fill),
+            svg_stroke(&e.stroke.into(), uv_fallback)
+        ),
+        Shape::LineSegment {
+            points: [a, b],
+            stroke,
+        } => format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {}/>",
+            a.x,
+            a.y,
+            b.x,
+            b.y,
+            svg_stroke(stroke, uv_fallback)
+        ),
+        Shape::Path(ps) => format!(
+            "<{} points=\"{}\" {} {}/>",
+            if ps.closed { "polygon" } else { "polyline" },
+            points(&ps.points),
+            svg_paint(
+                "fill",
+                if ps.closed {
+                    ps.fill
+                } else {
+                    egui::Color32::TRANSPARENT
+                }
+            ),
+            svg_stroke(&ps.stroke, uv_fallback)
+        ),
+        Shape::Rect(rs) => format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" {} {}/>",
+            rs.rect.min.x,
+            rs.rect.min.y,
+            rs.rect.width(),
+            rs.rect.height(),
+            rs.rounding
+                .nw
+                .max(rs.rounding.ne)
+                .max(rs.rounding.sw)
+                .max(rs.rounding.se),
+            svg_paint("fill", rs.fill),
+            svg_stroke(&rs.stroke.into(), uv_fallback)
+        ),
+        Shape::Text(ts) => {
+            let text = ts
+                .galley
+                .text()
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;");
+            let color = ts.override_text_color.unwrap_or(ts.fallback_color);
+            let rect = ts.galley.rect.translate(ts.pos.to_vec2());
+            format!(
+                "{}><title>{text}</title></rect>",
+                bounds("text", rect, color)
+            )
+        }
+        Shape::Mesh(m) => format!("{}/>", bounds("mesh", m.calc_bounds(), egui::Color32::GRAY)),
+        Shape::QuadraticBezier(q) => format!(
+            "<path d=\"M {} {} Q {} {} {} {}{}\" {} {}/>",
+            q.points[0].x,
+            q.points[0].y,
+            q.points[1].x,
+            q.points[1].y,
+            q.points[2].x,
+            q.points[2].y,
+            if q.closed { " Z" } else { "" },
+            svg_paint("fill", q.fill),
+            svg_stroke(&q.stroke, uv_fallback)
+        ),
+        Shape::CubicBezier(c) => format!(
+            "<path d=\"M {} {} C {} {} {} {} {} {}{}\" {} {}/>",
+            c.points[0].x,
+            c.points[0].y,
+            c.points[1].x,
+            c.points[1].y,
+            c.points[2].x,
+            c.points[2].y,
+            c.points[3].x,
+            c.points[3].y,
+            if c.closed { " Z" } else { "" },
+            svg_paint("fill", c.fill),
+            svg_stroke(&c.stroke, uv_fallback)
+        ),
+    };
+    svg.push_str(&element);
+    svg.push('\n');
 }
@@ -1,7 +1,21 @@
-use egui::{Color32, Context, Label, RichText, Rounding, Visuals};
+use egui::{Color32, Context, Hyperlink, RichText, Rounding, Visuals};
 use raylib::prelude::{Color, RaylibDraw};
 
-use crate::{input::InputOptions, RlEgui};
+use crate::{
+    base::{accumulate_scroll, flip_rect_y, key_repeats, monitor_size},
+    input::{
+        button_events, clipboard_text, dropped_file, letterbox, native_scale, pointer_in_region,
+        pointer_motion_events, pointer_moved, repaint_on_resize, touch_events,
+        touch_gesture_events, track_pointer, wheel_events, InputOptions, InputState,
+    },
+    paint::{
+        camera_clip_rect, color_image_to_rl, fits_texture, glyph_position, is_flat_fill,
+        mark_layers, ordered_texture_sets, raylib_cursor, repaint_delay, resolve_url, split_orders,
+        stroke_color,
+    },
+    util::{egui_rect_to_scissor, physical_key},
+    RlEgui,
+};
 
 #[derive(PartialEq, Debug)]
 enum TestEnum {
@@ -28,8 +42,12 @@ struct TestUi {
 //  2. ColorPicker (requires Meshes)
 //  3.
 
-fn doc_link_label(a: &str, _b: &str) -> Label {
-    Label::new(RichText::new(a).color(Color32::LIGHT_BLUE))
+/// Link to a search for `search_term` in egui's documentation, relative to its root (see [crate::BaseUrlHandler]).
+fn doc_link_label(title: &str, search_term: &str) -> Hyperlink {
+    Hyperlink::from_label_and_url(
+        RichText::new(title).color(Color32::LIGHT_BLUE),
+        format!("?search={search_term}"),
+    )
 }
 
 impl TestUi {
@@ -265,3 +283,1816 @@ fn it_works() {
         gui.draw(&mut d);
     }
 }
+
+#[test]
+#[ignore = "needs a display, e.g. run `xvfb-run cargo test -- --ignored`"]
+fn gallery_frames_paint() {
+    use egui::widgets::color_picker::{color_picker_color32, Alpha};
+    use raylib::prelude::WindowState;
+
+    let (mut rl, thread) = raylib::init().size(640, 480).title("Gallery").build();
+    rl.set_window_state(WindowState::default().set_window_hidden(true));
+
+    let mut test_ui = TestUi {
+        enabled: true,
+        visible: true,
+        radio: TestEnum::First,
+        opacity: 1.0,
+        boolean: false,
+        scalar: 0.0,
+        string: String::new(),
+        animate_progress_bar: true,
+    };
+    let mut open = true;
+    let mut color = Color32::GOLD;
+
+    let mut gui = RlEgui::default();
+    // Panic on anything that can't be drawn.
+    gui.set_strict_mode(true);
+    for _ in 0..10 {
+        gui.prepare(&mut rl, &thread, |ctx| {
+            test_ui.run(ctx, &mut open);
+            // Color pickers are drawn using meshes.
+            egui::Window::new("Color").show(ctx, |ui| {
+                color_picker_color32(ui, &mut color, Alpha::OnlyBlend);
+            });
+        });
+        assert!(gui.prs.as_ref().is_some_and(|p| !p.is_empty()));
+
+        let mut d = rl.begin_drawing(&thread);
+        d.clear_background(Color::WHITE);
+        gui.draw(&mut d);
+    }
+}
+
+/// Draw into a `size` render texture, cleared to black, in a hidden window, and read it back the right way up.
+/// `prepare` sets up what `draw` needs (e.g. a prepared [RlEgui]) before drawing begins.
+fn render_offscreen<S>(
+    size: (i32, i32),
+    prepare: impl FnOnce(&mut raylib::RaylibHandle, &raylib::RaylibThread) -> S,
+    draw: impl FnOnce(&mut S, &mut raylib::prelude::RaylibTextureMode<raylib::RaylibHandle>),
+) -> raylib::prelude::Image {
+    use raylib::prelude::{RaylibTexture2D, RaylibTextureModeExt, WindowState};
+
+    let (mut rl, thread) = raylib::init()
+        .size(size.0, size.1)
+        .title("Offscreen")
+        .build();
+    rl.set_window_state(WindowState::default().set_window_hidden(true));
+    let mut target = rl
+        .load_render_texture(&thread, size.0 as u32, size.1 as u32)
+        .expect("render texture should load");
+    let mut state = prepare(&mut rl, &thread);
+    {
+        let mut d = rl.begin_texture_mode(&thread, &mut target);
+        d.clear_background(Color::BLACK);
+        draw(&mut state, &mut d);
+    }
+    let mut image = target
+        .texture()
+        .load_image()
+        .expect("render texture should read back");
+    // Render textures are stored upside down.
+    image.flip_vertical();
+    image
+}
+
+#[test]
+#[ignore = "needs a display, e.g. run `xvfb-run cargo test -- --ignored --test-threads=1`"]
+fn closed_path_is_filled_and_outlined() {
+    let image = render_offscreen(
+        (64, 64),
+        |rl, thread| {
+            let mut gui = RlEgui::default();
+            gui.prepare(rl, thread, |ctx| {
+                let triangle = vec![
+                    egui::pos2(8.0, 56.0),
+                    egui::pos2(32.0, 8.0),
+                    egui::pos2(56.0, 56.0),
+                ];
+                ctx.layer_painter(egui::LayerId::background()).add(
+                    egui::epaint::PathShape::convex_polygon(
+                        triangle,
+                        Color32::GREEN,
+                        egui::Stroke::new(4.0, Color32::RED),
+                    ),
+                );
+            });
+            gui
+        },
+        |gui, d| gui.draw(d),
+    );
+    assert_eq!(image.get_color(32, 40), Color::new(0, 255, 0, 255), "fill");
+    // The bottom edge is the one closing the loop.
+    assert_eq!(
+        image.get_color(32, 56),
+        Color::new(255, 0, 0, 255),
+        "outline"
+    );
+}
+
+#[test]
+fn scroll_direction() {
+    use raylib::prelude::Vector2;
+
+    // Offset of a long list, half way down, after the wheel is rolled away from the user by one notch.
+    let scrolled = |invert_scroll: bool| {
+        let opt = InputOptions {
+            invert_scroll,
+            ..Default::default()
+        };
+        let pointer = egui::Event::PointerMoved(egui::pos2(100.0, 100.0));
+        let mut wheel = vec![pointer.clone()];
+        let mut state = InputState::default();
+        let up = Vector2::new(0.0, 1.0);
+        wheel_events(&opt, &mut state, up, egui::Modifiers::NONE, &mut wheel);
+        assert!(matches!(wheel[1], egui::Event::MouseWheel { .. }));
+
+        let ctx = Context::default();
+        let mut offset = 500.0;
+        // egui spreads the scroll over a few frames.
+        let still = vec![pointer];
+        let frames = [still.clone(), wheel, still.clone(), still];
+        for (i, events) in frames.into_iter().enumerate() {
+            let raw_input = egui::RawInput {
+                screen_rect: Some(egui::Rect::from_min_size(
+                    egui::Pos2::ZERO,
+                    egui::vec2(200.0, 200.0),
+                )),
+                events,
+                ..Default::default()
+            };
+            panel_frame(&ctx, raw_input, |ui| {
+                // Without shrinking, the area spans the panel, so it is under the pointer.
+                let mut area = egui::ScrollArea::vertical().auto_shrink(false);
+                if i == 0 {
+                    area = area.vertical_scroll_offset(offset);
+                }
+                offset = area
+                    .show(ui, |ui| {
+                        for item in 0..100 {
+                            ui.label(format!("Item {item}"));
+                        }
+                    })
+                    .state
+                    .offset
+                    .y;
+            });
+        }
+        offset
+    };
+    // Rolling the wheel up reveals the content above, unless scrolling is inverted.
+    assert!(scrolled(false) < 500.0);
+    assert!(scrolled(true) > 500.0);
+}
+
+#[test]
+fn scroll_accumulation() {
+    let mut accum = egui::Vec2::ZERO;
+    // Tiny deltas are held back until they add up to the threshold.
+    assert_eq!(accumulate_scroll(&mut accum, egui::vec2(0.0, 0.25), 1.0), None);
+    assert_eq!(accumulate_scroll(&mut accum, egui::vec2(0.0, 0.5), 1.0), None);
+    assert_eq!(
+        accumulate_scroll(&mut accum, egui::vec2(0.0, 0.25), 1.0),
+        Some(egui::vec2(0.0, 1.0))
+    );
+    assert_eq!(accum, egui::Vec2::ZERO);
+    // Without a threshold, everything passes through.
+    assert_eq!(
+        accumulate_scroll(&mut accum, egui::vec2(0.1, 0.0), 0.0),
+        Some(egui::vec2(0.1, 0.0))
+    );
+}
+
+#[test]
+fn key_repeat_frames() {
+    // No repeats before the delay, then one every interval.
+    let repeats: Vec<u32> = (0..40).filter(|&f| key_repeats(f, 30, 3)).collect();
+    assert_eq!(repeats, vec![30, 33, 36, 39]);
+    // A zero interval repeats every frame instead of dividing by zero.
+    assert!(key_repeats(31, 30, 0));
+}
+
+#[test]
+fn drag_value_follows_pointer() {
+    use raylib::prelude::Vector2;
+
+    let ctx = Context::default();
+    let mut value = 0.0f32;
+    let frame = |events: Vec<egui::Event>, value: &mut f32| {
+        let mut rect = egui::Rect::NOTHING;
+        let _ = ctx.run(
+            egui::RawInput {
+                events,
+                ..Default::default()
+            },
+            |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    rect = ui.add(egui::DragValue::new(value).speed(1.0)).rect;
+                });
+            },
+        );
+        rect
+    };
+
+    let center = frame(Vec::new(), &mut value).center();
+    let mut events = Vec::new();
+    pointer_motion_events(Vector2::new(center.x, center.y), Vector2::zero(), 1.0, 0, &mut events);
+    events.push(egui::Event::PointerButton {
+        pos: center,
+        button: egui::PointerButton::Primary,
+        pressed: true,
+        modifiers: Default::default(),
+    });
+    frame(events, &mut value);
+
+    // Drag to the right in two steps; the relative motion must be scaled like the position.
+    let mut pos = Vector2::new(center.x, center.y);
+    for _ in 0..2 {
+        let delta = Vector2::new(20.0, 0.0);
+        pos.x += delta.x;
+        let mut events = Vec::new();
+        pointer_motion_events(pos, delta, 1.0, 0, &mut events);
+        assert_eq!(events[0], egui::Event::MouseMoved(egui::vec2(20.0, 0.0)));
+        frame(events, &mut value);
+    }
+    assert!(value > 0.0, "Dragging right should increase the value, got {value}");
+
+    let mut events = Vec::new();
+    pointer_motion_events(Vector2::new(8.0, 4.0), Vector2::new(2.0, -2.0), 2.0, 0, &mut events);
+    assert_eq!(
+        events,
+        vec![
+            egui::Event::MouseMoved(egui::vec2(1.0, -1.0)),
+            egui::Event::PointerMoved(egui::pos2(4.0, 2.0)),
+        ]
+    );
+
+    // A fast stroke is filled in with evenly spaced positions, ending where the mouse is now.
+    let mut events = Vec::new();
+    pointer_motion_events(
+        Vector2::new(40.0, 0.0),
+        Vector2::new(40.0, 0.0),
+        1.0,
+        3,
+        &mut events,
+    );
+    assert_eq!(
+        events,
+        vec![
+            egui::Event::MouseMoved(egui::vec2(40.0, 0.0)),
+            egui::Event::PointerMoved(egui::pos2(10.0, 0.0)),
+            egui::Event::PointerMoved(egui::pos2(20.0, 0.0)),
+            egui::Event::PointerMoved(egui::pos2(30.0, 0.0)),
+            egui::Event::PointerMoved(egui::pos2(40.0, 0.0)),
+        ]
+    );
+}
+
+#[test]
+fn texture_upload_order() {
+    use egui::epaint::ImageDelta;
+    use egui::{ColorImage, TextureId, TextureOptions};
+
+    let image = || ColorImage::new([1, 1], Color32::WHITE);
+    let set = vec![
+        (
+            TextureId::Managed(0),
+            ImageDelta::partial([0, 0], image(), TextureOptions::LINEAR),
+        ),
+        (
+            TextureId::Managed(1),
+            ImageDelta::full(image(), TextureOptions::LINEAR),
+        ),
+        (
+            TextureId::Managed(0),
+            ImageDelta::full(image(), TextureOptions::LINEAR),
+        ),
+    ];
+    let order: Vec<_> = ordered_texture_sets(set)
+        .into_iter()
+        .map(|(id, delta)| (id, delta.is_whole()))
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            (TextureId::Managed(1), true),
+            (TextureId::Managed(0), true),
+            (TextureId::Managed(0), false),
+        ]
+    );
+}
+
+#[test]
+fn glyphs_snap_to_pixels() {
+    use raylib::prelude::Vector2;
+    let offset = egui::vec2(3.0, 7.0);
+    // Scrolling by sub-pixel amounts must not move rounded glyphs.
+    let a = glyph_position(Vector2::new(10.1, 20.2), offset, 1.5, true);
+    let b = glyph_position(Vector2::new(10.3, 20.4), offset, 1.5, true);
+    assert_eq!((a.x, a.y), (15.0, 31.0));
+    assert_eq!((a.x, a.y), (b.x, b.y));
+    let c = glyph_position(Vector2::new(10.3, 20.4), offset, 1.5, false);
+    assert!((c.x - 14.8).abs() < 1e-4 && (c.y - 30.9).abs() < 1e-4);
+}
+
+#[test]
+fn keypad_keys_are_distinct() {
+    use raylib::prelude::KeyboardKey;
+    assert_eq!(physical_key(KeyboardKey::KEY_ONE), Some(egui::Key::Num1));
+    assert_eq!(physical_key(KeyboardKey::KEY_KP_1), None);
+    assert_eq!(physical_key(KeyboardKey::KEY_ENTER), Some(egui::Key::Enter));
+    assert_eq!(physical_key(KeyboardKey::KEY_KP_ENTER), None);
+}
+
+#[test]
+fn focused_text_edit_repaints_soon() {
+    let ctx = Context::default();
+    let mut text = String::new();
+    let mut delay = std::time::Duration::MAX;
+    for frame in 0..3 {
+        let output = ctx.run(Default::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let response = ui.text_edit_singleline(&mut text);
+                if frame == 0 {
+                    response.request_focus();
+                }
+            });
+        });
+        delay = repaint_delay(&output);
+    }
+    // The cursor blinks, so egui must be repainted within the blink period.
+    assert!(delay < std::time::Duration::from_secs(1), "got {delay:?}");
+}
+
+#[test]
+fn still_pointer_lets_gui_idle() {
+    use raylib::prelude::Vector2;
+
+    let ctx = Context::default();
+    let mut last = None;
+    let mut delay = std::time::Duration::ZERO;
+    let pos = egui::pos2(20.0, 20.0);
+    // The pointer rests over an idle UI, after moving there in the first frame.
+    for frame in 0..4 {
+        let delta = if frame == 0 {
+            Vector2::new(20.0, 20.0)
+        } else {
+            Vector2::zero()
+        };
+        let mut events = Vec::new();
+        if pointer_moved(&mut last, pos, delta) {
+            pointer_motion_events(Vector2::new(pos.x, pos.y), delta, 1.0, 0, &mut events);
+        }
+        assert_eq!(events.is_empty(), frame > 0);
+        let raw_input = egui::RawInput {
+            events,
+            ..Default::default()
+        };
+        let output = ctx.run(raw_input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| ui.label("idle"));
+        });
+        delay = repaint_delay(&output);
+    }
+    assert_ne!(delay, std::time::Duration::ZERO);
+
+    // Moving again, or coming back after leaving, is sent.
+    assert!(pointer_moved(&mut last, pos, Vector2::new(1.0, 0.0)));
+    assert!(pointer_moved(&mut None, pos, Vector2::zero()));
+}
+
+#[test]
+fn zero_monitor_size_falls_back_to_window() {
+    let window = egui::vec2(800.0, 600.0);
+    assert_eq!(
+        monitor_size((1920, 1080), window, 2.0),
+        egui::vec2(960.0, 540.0)
+    );
+    assert_eq!(monitor_size((0, 0), window, 2.0), egui::vec2(400.0, 300.0));
+    assert_eq!(monitor_size((1920, 0), window, 1.0), window);
+}
+
+#[test]
+fn flipped_rect_position() {
+    // A 100x20 title bar at the top of a 600 point tall target ends up at its bottom.
+    let rect = egui::Rect::from_min_size(egui::pos2(10.0, 0.0), egui::vec2(100.0, 20.0));
+    let flipped = flip_rect_y(rect, 600.0);
+    assert_eq!(
+        flipped,
+        egui::Rect::from_min_size(egui::pos2(10.0, 580.0), egui::vec2(100.0, 20.0))
+    );
+    assert_eq!(flip_rect_y(flipped, 600.0), rect);
+}
+
+#[test]
+fn flipped_scissor_position() {
+    // A 100x20 point title bar at the top of an 800x600 pixel screen, at 2 pixels per point.
+    let rect = egui::Rect::from_min_size(egui::pos2(10.0, 0.0), egui::vec2(100.0, 20.0));
+    let screen = egui::vec2(800.0, 600.0);
+    let mut painter = crate::paint::Painter::default();
+    assert_eq!(painter.scissor(rect, 2.0, screen), Some((20, 0, 200, 40)));
+    painter.flip_y = true;
+    assert_eq!(painter.scissor(rect, 2.0, screen), Some((20, 560, 200, 40)));
+    // Whatever hangs off the top ends up off the bottom, where it's cut off.
+    let overhang = rect.translate(egui::vec2(0.0, -5.0));
+    let scissor = painter.scissor(overhang, 2.0, screen);
+    assert_eq!(scissor, Some((20, 580, 200, 20)));
+}
+
+#[test]
+fn pointer_filtered_by_region() {
+    let region = raylib::prelude::Rectangle::new(0.0, 0.0, 200.0, 600.0);
+    assert!(pointer_in_region(egui::pos2(100.0, 300.0), Some(region)));
+    assert!(!pointer_in_region(egui::pos2(300.0, 300.0), Some(region)));
+    assert!(pointer_in_region(egui::pos2(300.0, 300.0), None));
+}
+
+#[test]
+fn unmapped_keys_are_ignored() {
+    use crate::util::ConvertRE;
+    use raylib::prelude::KeyboardKey;
+    for kk in [
+        KeyboardKey::KEY_NULL,
+        KeyboardKey::KEY_KP_MULTIPLY,
+        KeyboardKey::KEY_CAPS_LOCK,
+        KeyboardKey::KEY_SCROLL_LOCK,
+        KeyboardKey::KEY_NUM_LOCK,
+        KeyboardKey::KEY_PRINT_SCREEN,
+        KeyboardKey::KEY_PAUSE,
+        KeyboardKey::KEY_KB_MENU,
+        KeyboardKey::KEY_LEFT_SHIFT,
+        KeyboardKey::KEY_RIGHT_SUPER,
+    ] {
+        let key: Option<egui::Key> = kk.convert();
+        assert_eq!(key, None, "{kk:?}");
+    }
+    let key: Option<egui::Key> = KeyboardKey::KEY_SPACE.convert();
+    assert_eq!(key, Some(egui::Key::Space));
+}
+
+#[test]
+fn short_image_data_is_rejected() {
+    let err = crate::util::try_image_from_rgba([4, 4], &[0; 60]).unwrap_err();
+    assert_eq!(err.expected, 64);
+    assert_eq!(err.actual, 60);
+}
+
+#[test]
+fn convert_to_names_target() {
+    use crate::util::{ConvertTo, PaletteRl};
+    let v = egui::pos2(3.0, 4.0).convert_to::<raylib::prelude::Vector2>();
+    assert_eq!((v.x, v.y), (3.0, 4.0));
+    let palette = PaletteRl::from(&Visuals::dark());
+    assert_eq!(
+        palette.text,
+        Visuals::dark().text_color().convert_to::<Color>()
+    );
+}
+
+#[test]
+fn clip_rect_follows_camera() {
+    let rect = egui::Rect::from_min_size(egui::pos2(100.0, 100.0), egui::vec2(50.0, 20.0));
+    let camera = raylib::camera::Camera2D {
+        offset: raylib::prelude::Vector2::new(400.0, 300.0),
+        target: raylib::prelude::Vector2::new(100.0, 100.0),
+        rotation: 0.0,
+        zoom: 2.0,
+    };
+    assert_eq!(
+        camera_clip_rect(rect, &camera),
+        egui::Rect::from_min_size(egui::pos2(400.0, 300.0), egui::vec2(100.0, 40.0))
+    );
+}
+
+#[test]
+fn modifier_sides_agree() {
+    use crate::input::ModifierKeys;
+    let left = ModifierKeys {
+        left_shift: true,
+        ..Default::default()
+    };
+    let right = ModifierKeys {
+        right_shift: true,
+        ..Default::default()
+    };
+    assert_ne!(left, right);
+    assert_eq!(left.modifiers(), right.modifiers());
+    assert!(left.modifiers().shift);
+}
+
+#[test]
+fn chord_keeps_modifier_released_in_same_frame() {
+    use crate::input::ModifierKeys;
+    // Ctrl+S, with ctrl let go before the end of the frame in which S went down.
+    let held = ModifierKeys::default();
+    let released = ModifierKeys {
+        left_ctrl: true,
+        ..Default::default()
+    };
+    assert!(!held.modifiers().ctrl);
+    let chord = held.or(released).modifiers();
+    assert!(chord.ctrl);
+    assert!(!chord.shift);
+
+    let ctx = Context::default();
+    let save = egui::KeyboardShortcut::new(egui::Modifiers::CTRL, egui::Key::S);
+    let mut saved = false;
+    let _ = ctx.run(
+        egui::RawInput {
+            events: vec![egui::Event::Key {
+                key: egui::Key::S,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers: chord,
+            }],
+            modifiers: held.modifiers(),
+            ..Default::default()
+        },
+        |ctx| saved = ctx.input_mut(|i| i.consume_shortcut(&save)),
+    );
+    assert!(saved);
+}
+
+#[test]
+fn paste_keeps_lines_and_invalid_text() {
+    assert_eq!(
+        clipboard_text(b"first\r\nsecond\nthird"),
+        ("first\nsecond\nthird".to_owned(), false)
+    );
+    let (text, lossy) = clipboard_text(b"caf\xe9 au lait");
+    assert!(lossy);
+    assert_eq!(text, "caf\u{FFFD} au lait");
+}
+
+#[test]
+fn zoom_is_clamped() {
+    let gui = RlEgui::default();
+    // egui applies a new zoom when the next frame begins.
+    let zoom_after = |change: &dyn Fn(&RlEgui)| {
+        change(&gui);
+        let _ = gui.ctx.run(Default::default(), |_| {});
+        gui.ctx.zoom_factor()
+    };
+    assert_eq!(zoom_after(&|gui| gui.set_zoom(10.0)), 4.0);
+    assert!(zoom_after(&|gui| gui.zoom_out()) < 4.0);
+    assert_eq!(zoom_after(&|gui| gui.set_zoom(0.1)), 0.5);
+    assert_eq!(zoom_after(&|gui| gui.zoom_out()), 0.5);
+}
+
+#[test]
+fn keyboard_zoom_is_not_applied_twice() {
+    use egui::{Event, Key, Modifiers};
+
+    // Ctrl and plus also reaches egui as a key press, which must not zoom on top of RlEgui::set_zoom.
+    let gui = RlEgui::default();
+    let raw_input = egui::RawInput {
+        events: vec![Event::Key {
+            key: Key::Equals,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: Modifiers::COMMAND,
+        }],
+        modifiers: Modifiers::COMMAND,
+        ..Default::default()
+    };
+    // Any zoom would be applied when the next frame begins.
+    let _ = gui.ctx.run(raw_input, |_| {});
+    let _ = gui.ctx.run(Default::default(), |_| {});
+    assert_eq!(gui.ctx.zoom_factor(), 1.0);
+}
+
+#[test]
+fn shapes_split_by_order() {
+    let ctx = egui::Context::default();
+    let run = |ctx: &egui::Context| {
+        mark_layers(ctx);
+        egui::CentralPanel::default().show(ctx, |ui| ui.label("background"));
+        let tooltip = egui::LayerId::new(egui::layers::Order::Tooltip, egui::Id::new("tip"));
+        ctx.layer_painter(tooltip)
+            .circle_filled(egui::pos2(10.0, 10.0), 5.0, Color32::RED);
+    };
+    // The first pass of new areas is spent measuring them.
+    let _ = ctx.run(egui::RawInput::default(), run);
+    let output = ctx.run(egui::RawInput::default(), run);
+    let (shapes, orders) = split_orders(output.shapes);
+    assert_eq!(shapes.len(), orders.len());
+    assert!(orders.windows(2).all(|w| w[0] <= w[1]));
+    assert_eq!(orders.first(), Some(&egui::layers::Order::Background));
+    assert_eq!(orders.last(), Some(&egui::layers::Order::Tooltip));
+    assert!(!shapes
+        .iter()
+        .any(|s| matches!(s.shape, egui::Shape::Callback(_))));
+}
+
+#[test]
+fn unmarked_shapes_are_background() {
+    use egui::layers::Order;
+
+    let ctx = egui::Context::default();
+    let output = ctx.run(egui::RawInput::default(), |ctx| {
+        for order in [Order::Foreground, Order::Tooltip] {
+            let layer = egui::LayerId::new(order, egui::Id::new(order));
+            ctx.layer_painter(layer)
+                .circle_filled(egui::pos2(10.0, 10.0), 5.0, Color32::RED);
+        }
+    });
+    // Without `mark_layers`, as when `RlEgui::set_split_layers` wasn't enabled.
+    let (shapes, orders) = split_orders(output.shapes);
+    assert_eq!(shapes.len(), 2);
+    assert_eq!(orders, vec![Order::Background; 2]);
+}
+
+#[test]
+fn window_scale_is_followed() {
+    use raylib::prelude::Vector2;
+
+    // E.g. the window moved to a monitor with twice the DPI.
+    let window_scale = Vector2::new(2.0, 2.0);
+    let mut opt = InputOptions::default();
+    assert_eq!(native_scale(&opt, None, window_scale), 1.0);
+    opt.follow_window_scale = true;
+    assert_eq!(native_scale(&opt, None, window_scale), 2.0);
+    // A scale given for the frame wins.
+    assert_eq!(native_scale(&opt, Some(1.5), window_scale), 1.5);
+}
+
+#[test]
+#[ignore = "needs a display, e.g. run `xvfb-run cargo test -- --ignored --test-threads=1`"]
+fn scale_change_reuploads_font_atlas() {
+    use raylib::prelude::WindowState;
+
+    let (mut rl, thread) = raylib::init().size(320, 240).title("Rescale").build();
+    rl.set_window_state(WindowState::default().set_window_hidden(true));
+    let mut gui = RlEgui::default();
+    gui.set_keep_raw_input(true);
+    let mut atlas_sizes = Vec::new();
+    for scale in [1.0, 2.0] {
+        gui.input_options_mut().native_pixels_per_point = scale;
+        gui.prepare(&mut rl, &thread, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| ui.label("crisp"));
+        });
+        let raw_input = gui.last_raw_input().expect("raw input should be kept");
+        let viewport = &raw_input.viewports[&egui::ViewportId::ROOT];
+        assert_eq!(viewport.native_pixels_per_point, Some(scale));
+        atlas_sizes.push(gui.ctx.fonts(|f| f.image().size));
+    }
+    // egui rasterized its fonts anew for the new scale, and the painter took the whole atlas again.
+    assert_ne!(atlas_sizes[0], atlas_sizes[1]);
+    let font = gui
+        .painter
+        .unregister_texture(egui::TextureId::default())
+        .expect("font texture should be loaded");
+    assert_eq!([font.width as usize, font.height as usize], atlas_sizes[1]);
+}
+
+#[test]
+fn configured_before_first_frame() {
+    let style = egui::Style {
+        animation_time: 0.0,
+        ..Default::default()
+    };
+    let gui = RlEgui::default()
+        .with_style(style)
+        .configure(|ctx| ctx.set_visuals(Visuals::light()));
+    assert_eq!(gui.ctx.style().animation_time, 0.0);
+    assert!(!gui.ctx.style().visuals.dark_mode);
+}
+
+#[test]
+fn nested_scissor_stays_within_parent() {
+    let outer = egui::Rect::from_min_max(egui::pos2(10.3, 20.7), egui::pos2(210.0, 120.0));
+    let contains = |(ox, oy, ow, oh): (i32, i32, i32, i32),
+                    (ix, iy, iw, ih): (i32, i32, i32, i32)| {
+        ix >= ox && iy >= oy && ix + iw <= ox + ow && iy + ih <= oy + oh
+    };
+    let bounds = (1000, 1000);
+    for pxpp in [1.0, 1.25, 1.5, 1.75, 2.0] {
+        for step in 0..10 {
+            // An inner scroll area in the bottom right of the outer one, a fraction of a point in.
+            let inset = egui::Vec2::splat(step as f32 * 0.1);
+            let inner = egui::Rect::from_min_max(outer.min + inset, outer.max);
+            let scissor = |r| egui_rect_to_scissor(r, pxpp, bounds).expect("rect is on screen");
+            assert!(
+                contains(scissor(outer), scissor(inner)),
+                "pxpp {pxpp}, inset {inset:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn scissor_covers_touched_pixels_within_bounds() {
+    let rect = egui::Rect::from_min_max(egui::pos2(1.2, 2.5), egui::pos2(10.1, 20.0));
+    assert_eq!(
+        egui_rect_to_scissor(rect, 2.0, (100, 100)),
+        Some((2, 5, 19, 35))
+    );
+    // Clamped to the bounds.
+    assert_eq!(
+        egui_rect_to_scissor(rect, 2.0, (10, 30)),
+        Some((2, 5, 8, 25))
+    );
+    assert_eq!(
+        egui_rect_to_scissor(egui::Rect::EVERYTHING, 1.5, (64, 48)),
+        Some((0, 0, 64, 48))
+    );
+    // Empty, inverted and off-screen rects leave nothing.
+    let off_screen = rect.translate(egui::vec2(-20.0, 0.0));
+    for rect in [
+        egui::Rect::NOTHING,
+        off_screen,
+        rect.translate(egui::vec2(0.0, 60.0)),
+    ] {
+        assert_eq!(
+            egui_rect_to_scissor(rect, 2.0, (100, 100)),
+            None,
+            "{rect:?}"
+        );
+    }
+}
+
+#[test]
+fn textured_rects_are_sampled() {
+    let rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(10.0, 10.0));
+    // What egui sends for a plain filled rect.
+    let plain = egui::epaint::RectShape::filled(rect, Rounding::ZERO, Color32::RED);
+    assert!(is_flat_fill(&plain));
+    // A user texture is drawn even at zero uv, since it isn't known to be white there.
+    let user = egui::epaint::RectShape {
+        fill_texture_id: egui::TextureId::User(0),
+        ..plain
+    };
+    assert!(!is_flat_fill(&user));
+    let atlas_region = egui::epaint::RectShape {
+        uv: egui::Rect::from_min_max(egui::pos2(0.5, 0.5), egui::pos2(1.0, 1.0)),
+        ..plain
+    };
+    assert!(!is_flat_fill(&atlas_region));
+}
+
+#[test]
+fn two_finger_gestures_pan_and_pinch() {
+    let mut gesture = None;
+    let mut events = Vec::new();
+    let mut touch = |p0: (f32, f32), p1: (f32, f32), events: &mut Vec<egui::Event>| {
+        let fingers = [(0, egui::pos2(p0.0, p0.1)), (1, egui::pos2(p1.0, p1.1))];
+        touch_gesture_events(&mut gesture, Some(fingers), egui::Modifiers::NONE, events);
+    };
+    touch((100.0, 100.0), (200.0, 100.0), &mut events);
+    // Within the slop nothing happens.
+    touch((102.0, 100.0), (202.0, 100.0), &mut events);
+    assert!(events.is_empty());
+    // Past it, only the last frame's motion scrolls.
+    touch((112.0, 100.0), (212.0, 100.0), &mut events);
+    assert_eq!(
+        events,
+        [egui::Event::MouseWheel {
+            unit: egui::MouseWheelUnit::Point,
+            delta: egui::vec2(10.0, 0.0),
+            modifiers: egui::Modifiers::NONE,
+        }]
+    );
+    events.clear();
+    // Spreading the fingers evenly zooms in without panning.
+    touch((62.0, 100.0), (262.0, 100.0), &mut events);
+    assert_eq!(events, [egui::Event::Zoom(2.0)]);
+}
+
+#[test]
+fn color_images_keep_their_channels() {
+    let translucent = Color32::from_rgba_unmultiplied(200, 100, 50, 128);
+    // Opaque images are copied in one go, others are unmultiplied pixel by pixel.
+    for pixels in [
+        vec![Color32::RED, Color32::from_rgb(1, 2, 3), Color32::BLUE],
+        vec![Color32::RED, Color32::from_rgb(1, 2, 3), translucent],
+    ] {
+        let image = egui::ColorImage {
+            size: [3, 1],
+            pixels,
+        };
+        let mut rl_image = color_image_to_rl(&image);
+        for (x, pixel) in image.pixels.iter().enumerate() {
+            let [r, g, b, a] = pixel.to_srgba_unmultiplied();
+            assert_eq!(
+                rl_image.get_color(x as i32, 0),
+                Color::new(r, g, b, a),
+                "{pixel:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn background_thread_wakes_idle_gui() {
+    use std::time::Duration;
+
+    let gui = RlEgui::default();
+    let idle = || {
+        // The first frames lay out the gui, after which it idles. Preparing a frame clears the request first.
+        for _ in 0..5 {
+            gui.take_repaint_request();
+            let _ = gui.ctx.run(Default::default(), |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| ui.label("idle"));
+            });
+        }
+        assert!(!gui.needs_repaint());
+    };
+    let wake = |delay| {
+        let worker = gui.ctx.clone();
+        std::thread::spawn(move || worker.request_repaint_after(delay))
+            .join()
+            .expect("worker should not panic");
+    };
+
+    idle();
+    wake(Duration::ZERO);
+    assert!(gui.needs_repaint());
+
+    // Delayed requests between frames are only due once their time comes, but aren't forgotten by frames before.
+    idle();
+    wake(Duration::from_millis(50));
+    assert!(!gui.needs_repaint());
+    gui.take_repaint_request();
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(gui.needs_repaint());
+}
+
+#[cfg(feature = "svg")]
+#[test]
+fn shapes_export_to_svg() {
+    let mut svg = String::new();
+    let circle = egui::Shape::circle_filled(egui::pos2(10.0, 20.0), 5.0, Color32::RED);
+    let line = egui::Shape::line_segment(
+        [egui::pos2(0.0, 0.0), egui::pos2(4.0, 3.0)],
+        egui::Stroke::new(2.0, Color32::BLUE),
+    );
+    // Strokes colored by position get the painter's fallback, like on screen.
+    let uv_line = egui::Shape::LineSegment {
+        points: [egui::pos2(0.0, 0.0), egui::pos2(4.0, 3.0)],
+        stroke: egui::epaint::PathStroke::new_uv(1.0, |_, _| Color32::RED),
+    };
+    let shapes = egui::Shape::Vec(vec![circle, line, uv_line]);
+    crate::paint::write_svg_shape(&mut svg, &shapes, Color::new(0, 255, 0, 255));
+    assert_eq!(
+        svg,
+        "<circle cx=\"10\" cy=\"20\" r=\"5\" fill=\"#ff0000\" fill-opacity=\"1\" stroke=\"none\"/>\n\
+         <line x1=\"0\" y1=\"0\" x2=\"4\" y2=\"3\" stroke=\"#0000ff\" stroke-opacity=\"1\" stroke-width=\"2\"/>\n\
+         <line x1=\"0\" y1=\"0\" x2=\"4\" y2=\"3\" stroke=\"#00ff00\" stroke-opacity=\"1\" stroke-width=\"1\"/>\n"
+    );
+}
+
+#[test]
+fn touch_phases_follow_fingers() {
+    let phases = |events: &[egui::Event]| -> Vec<_> {
+        events
+            .iter()
+            .map(|e| match e {
+                egui::Event::Touch {
+                    id, phase, force, ..
+                } => {
+                    // Raylib knows no pressure.
+                    assert_eq!(*force, None);
+                    (id.0, *phase)
+                }
+                e => panic!("unexpected event {e:?}"),
+            })
+            .collect()
+    };
+    let mut last = Default::default();
+    let mut events = Vec::new();
+    touch_events(&mut last, &[(3, egui::pos2(1.0, 1.0))], &mut events);
+    assert_eq!(phases(&events), [(3, egui::TouchPhase::Start)]);
+    events.clear();
+    // Holding still sends nothing.
+    touch_events(&mut last, &[(3, egui::pos2(1.0, 1.0))], &mut events);
+    assert!(events.is_empty());
+    touch_events(&mut last, &[(3, egui::pos2(2.0, 1.0))], &mut events);
+    touch_events(&mut last, &[], &mut events);
+    assert_eq!(
+        phases(&events),
+        [(3, egui::TouchPhase::Move), (3, egui::TouchPhase::End)]
+    );
+}
+
+#[test]
+fn rect_and_path_strokes_agree() {
+    let color = Color32::from_rgba_unmultiplied(200, 100, 50, 128);
+    let rect_stroke = egui::Stroke::new(2.0, color);
+    let path_stroke = egui::epaint::PathStroke::new(2.0, color);
+    assert_eq!(
+        stroke_color(&rect_stroke.into(), Color::GRAY),
+        stroke_color(&path_stroke, Color::GRAY)
+    );
+
+    let gradient = egui::epaint::PathStroke::new_uv(2.0, |_, _| Color32::RED);
+    assert_eq!(stroke_color(&gradient, Color::GRAY), Color::GRAY);
+    assert_eq!(stroke_color(&gradient, Color::MAGENTA), Color::MAGENTA);
+}
+
+#[test]
+fn click_within_a_frame_is_kept() {
+    let pos = egui::pos2(5.0, 5.0);
+    let mut events = Vec::new();
+    button_events(
+        egui::PointerButton::Primary,
+        pos,
+        true,
+        true,
+        false,
+        egui::Modifiers::NONE,
+        &mut events,
+    );
+    let pressed: Vec<_> = events
+        .iter()
+        .map(|e| match e {
+            egui::Event::PointerButton { pressed, .. } => *pressed,
+            e => panic!("unexpected event {e:?}"),
+        })
+        .collect();
+    assert_eq!(pressed, [true, false]);
+}
+
+/// Run a frame of `ctx` on `raw_input`, with `add_contents` in a central panel.
+fn panel_frame(ctx: &Context, raw_input: egui::RawInput, add_contents: impl FnOnce(&mut egui::Ui)) {
+    let _ = ctx.run(raw_input, |ctx| {
+        egui::CentralPanel::default().show(ctx, add_contents);
+    });
+}
+
+#[test]
+fn escape_closes_popup() {
+    use raylib::prelude::KeyboardKey;
+
+    let key_map = InputOptions::default().key_map;
+    assert_eq!(key_map.get(&KeyboardKey::KEY_TAB), Some(&egui::Key::Tab));
+    let key = key_map[&KeyboardKey::KEY_ESCAPE];
+
+    let ctx = Context::default();
+    let popup_id = egui::Id::new("popup");
+    let frame = |events: Vec<egui::Event>| {
+        let raw_input = egui::RawInput {
+            events,
+            ..Default::default()
+        };
+        panel_frame(&ctx, raw_input, |ui| {
+            let response = ui.button("Menu");
+            egui::popup_below_widget(
+                ui,
+                popup_id,
+                &response,
+                egui::PopupCloseBehavior::CloseOnClickOutside,
+                |ui| ui.label("Item"),
+            );
+        });
+    };
+    ctx.memory_mut(|mem| mem.open_popup(popup_id));
+    frame(Vec::new());
+    assert!(ctx.memory(|mem| mem.is_popup_open(popup_id)));
+    frame(vec![egui::Event::Key {
+        key,
+        physical_key: None,
+        pressed: true,
+        repeat: false,
+        modifiers: Default::default(),
+    }]);
+    assert!(!ctx.memory(|mem| mem.is_popup_open(popup_id)));
+}
+
+#[test]
+fn tab_moves_focus_in_order() {
+    use raylib::prelude::KeyboardKey;
+
+    let key = InputOptions::default().key_map[&KeyboardKey::KEY_TAB];
+    let ctx = Context::default();
+    let mut fields = [String::new(), String::new(), String::new()];
+    let mut frame = |events: Vec<egui::Event>| {
+        let mut ids = Vec::new();
+        let raw_input = egui::RawInput {
+            events,
+            ..Default::default()
+        };
+        panel_frame(&ctx, raw_input, |ui| {
+            for field in fields.iter_mut() {
+                ids.push(ui.text_edit_singleline(field).id);
+            }
+        });
+        ids
+    };
+    let tab = |modifiers| {
+        vec![egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers,
+        }]
+    };
+    let mut focus_after = |modifiers| {
+        // Focus may only move on the frame after the key, e.g. backwards.
+        let ids = frame(tab(modifiers));
+        frame(Vec::new());
+        let focused = ctx.memory(|mem| mem.focused());
+        ids.iter().position(|&id| Some(id) == focused)
+    };
+    assert_eq!(focus_after(egui::Modifiers::NONE), Some(0));
+    assert_eq!(focus_after(egui::Modifiers::NONE), Some(1));
+    assert_eq!(focus_after(egui::Modifiers::NONE), Some(2));
+    assert_eq!(focus_after(egui::Modifiers::SHIFT), Some(1));
+    assert_eq!(focus_after(egui::Modifiers::SHIFT), Some(0));
+}
+
+#[test]
+#[ignore = "needs a display, e.g. run `xvfb-run cargo test -- --ignored --test-threads=1`"]
+fn global_alpha_fades_everything() {
+    let red_at = |alpha| {
+        let image = render_offscreen(
+            (64, 64),
+            |rl, thread| {
+                let mut gui = RlEgui::default();
+                gui.set_global_alpha(alpha);
+                gui.prepare(rl, thread, |ctx| {
+                    ctx.layer_painter(egui::LayerId::background()).rect_filled(
+                        egui::Rect::from_min_max(egui::pos2(8.0, 8.0), egui::pos2(56.0, 56.0)),
+                        0.0,
+                        Color32::RED,
+                    );
+                });
+                gui
+            },
+            |gui, d| gui.draw(d),
+        );
+        image.get_color(32, 32).r
+    };
+    assert_eq!(red_at(1.0), 255);
+    // Half as opaque over black is half as bright.
+    let red = red_at(0.5);
+    assert!(red.abs_diff(128) <= 2, "got {red}");
+}
+
+#[test]
+fn shift_click_selects_range() {
+    let ctx = Context::default();
+    let mut selected = [false; 5];
+    let mut anchor = None;
+    let mut frame = |events: Vec<egui::Event>, modifiers: egui::Modifiers| {
+        let mut rects = Vec::new();
+        let raw_input = egui::RawInput {
+            events,
+            modifiers,
+            ..Default::default()
+        };
+        panel_frame(&ctx, raw_input, |ui| {
+            for i in 0..selected.len() {
+                let response = ui.selectable_label(selected[i], format!("Item {i}"));
+                rects.push(response.rect);
+                if !response.clicked() {
+                    continue;
+                }
+                match anchor {
+                    Some(a) if ui.input(|input| input.modifiers.shift) => {
+                        for (j, s) in selected.iter_mut().enumerate() {
+                            *s = (a.min(i)..=a.max(i)).contains(&j);
+                        }
+                    }
+                    _ => {
+                        selected = [false; 5];
+                        selected[i] = true;
+                        anchor = Some(i);
+                    }
+                }
+            }
+        });
+        rects
+    };
+    let rects = frame(Vec::new(), egui::Modifiers::NONE);
+    // A click as gathered: the events carry the same modifiers as the frame.
+    let mut click = |i: usize, modifiers| {
+        let pos = rects[i].center();
+        for (pressed, released) in [(true, false), (false, true)] {
+            let mut events = vec![egui::Event::PointerMoved(pos)];
+            button_events(
+                egui::PointerButton::Primary,
+                pos,
+                pressed,
+                released,
+                pressed,
+                modifiers,
+                &mut events,
+            );
+            frame(events, modifiers);
+        }
+    };
+    click(1, egui::Modifiers::NONE);
+    click(3, egui::Modifiers::SHIFT);
+    assert_eq!(selected, [false, true, true, true, false]);
+}
+
+#[test]
+fn borrowed_textures_are_never_unloaded() {
+    // Without a window there's no GL context, so unloading this would crash.
+    let texture = unsafe {
+        raylib::texture::WeakTexture2D::from_raw(raylib::ffi::Texture2D {
+            id: 7,
+            width: 16,
+            height: 16,
+            mipmaps: 1,
+            format: raylib::ffi::PixelFormat::PIXELFORMAT_UNCOMPRESSED_R8G8B8A8 as i32,
+        })
+    };
+    let mut painter = crate::paint::Painter::default();
+    let id = unsafe { painter.register_texture_ref(&texture) };
+    assert!(painter.unregister_texture_ref(id));
+    assert!(!painter.unregister_texture_ref(id));
+
+    let id = unsafe { painter.register_texture_ref(&texture) };
+    assert!(painter.unregister_texture(id).is_none());
+    // Still registered when the painter goes away.
+    let _ = unsafe { painter.register_texture_ref(&texture) };
+    drop(painter);
+}
+
+#[test]
+fn relative_urls_resolve_against_base() {
+    let base = "https://docs.rs/egui/latest/egui/index.html?x=1#top";
+    for (url, resolved) in [
+        ("https://example.com/", "https://example.com/"),
+        ("mailto:someone@example.com", "mailto:someone@example.com"),
+        (
+            "widgets/struct.Button.html",
+            "https://docs.rs/egui/latest/egui/widgets/struct.Button.html",
+        ),
+        (
+            "../epaint/index.html#shapes",
+            "https://docs.rs/egui/latest/epaint/index.html#shapes",
+        ),
+        ("./", "https://docs.rs/egui/latest/egui/"),
+        ("/about", "https://docs.rs/about"),
+        (
+            "?search=label",
+            "https://docs.rs/egui/latest/egui/index.html?search=label",
+        ),
+        (
+            "#method.new",
+            "https://docs.rs/egui/latest/egui/index.html?x=1#method.new",
+        ),
+        ("//example.com/a", "https://example.com/a"),
+    ] {
+        assert_eq!(resolve_url(base, url), resolved, "{url}");
+    }
+    assert_eq!(
+        resolve_url("https://docs.rs/egui", "?search=label"),
+        "https://docs.rs/egui?search=label"
+    );
+    // A base without a path, but with a query.
+    assert_eq!(resolve_url("https://a.com?x", "foo"), "https://a.com/foo");
+    assert_eq!(resolve_url("https://a.com?x", "?y"), "https://a.com?y");
+}
+
+#[test]
+fn repaint_requests_are_flagged() {
+    let gui = RlEgui::default();
+    assert!(!gui.needs_repaint());
+    let worker = gui.ctx.clone();
+    std::thread::spawn(move || worker.request_repaint())
+        .join()
+        .expect("worker should not panic");
+    assert!(gui.needs_repaint());
+}
+
+#[test]
+fn resize_requests_another_frame() {
+    let gui = RlEgui::default();
+    let run = |resized| {
+        repaint_on_resize(&gui.ctx, resized);
+        gui.ctx.run(Default::default(), |ctx| {
+            egui::SidePanel::left("tools").show(ctx, |ui| ui.label("Tools"));
+        })
+    };
+    // Let the first frames, which egui repaints on its own, pass.
+    for _ in 0..3 {
+        run(false);
+    }
+    assert_ne!(repaint_delay(&run(false)), std::time::Duration::ZERO);
+    gui.take_repaint_request();
+
+    let output = run(true);
+    assert!(gui.needs_repaint());
+    assert_eq!(repaint_delay(&output), std::time::Duration::ZERO);
+}
+
+#[test]
+fn growing_font_atlas_keeps_glyphs_in_texture() {
+    let ctx = Context::default();
+    let glyphs: Vec<char> = (0x20..0x2000).filter_map(char::from_u32).collect();
+    // The font texture's size, as the painter would track it.
+    let mut size = [0, 0];
+    for chunk in glyphs.chunks(256) {
+        let text: String = chunk.iter().collect();
+        let output = ctx.run(Default::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| ui.label(&text));
+        });
+        let mut stale = false;
+        for (id, delta) in &output.textures_delta.set {
+            if *id != egui::TextureId::default() {
+                continue;
+            }
+            let [w, h] = delta.image.size();
+            match delta.pos {
+                None => size = [w as i32, h as i32],
+                Some([x, y]) => stale |= !fits_texture(size, [x + w, y + h]),
+            }
+        }
+        if stale {
+            // What the next prepare uploads.
+            let [w, h] = ctx.fonts(|f| f.image().size);
+            size = [w as i32, h as i32];
+        }
+        for shape in &output.shapes {
+            let egui::Shape::Text(ts) = &shape.shape else {
+                continue;
+            };
+            for g in ts.galley.rows.iter().flat_map(|r| &r.glyphs) {
+                let max = [g.uv_rect.max[0] as usize, g.uv_rect.max[1] as usize];
+                assert!(fits_texture(size, max), "{:?} outside {size:?}", g.chr);
+            }
+        }
+    }
+}
+
+#[test]
+#[ignore = "needs a display, e.g. run `xvfb-run cargo test -- --ignored --test-threads=1`"]
+fn clip_runs_draw_like_single_shapes() {
+    // Scissor mode is entered once per run of shapes sharing a clip rect, or once per shape.
+    let render = |single: bool| {
+        render_offscreen(
+            (320, 240),
+            |rl, thread| {
+                let mut test_ui = TestUi {
+                    enabled: true,
+                    visible: true,
+                    radio: TestEnum::First,
+                    opacity: 1.0,
+                    boolean: false,
+                    scalar: 0.0,
+                    string: String::new(),
+                    animate_progress_bar: false,
+                };
+                let mut open = true;
+                let mut gui = RlEgui::default();
+                for _ in 0..3 {
+                    gui.prepare(rl, thread, |ctx| test_ui.run(ctx, &mut open));
+                }
+                gui
+            },
+            |gui, d| {
+                let prs = gui.prs.take().expect("shapes should be prepared");
+                let everything = egui::Rect::EVERYTHING;
+                if single {
+                    for i in 0..prs.len() {
+                        gui.painter
+                            .paint_shapes(&prs, i..i + 1, d, everything, None);
+                    }
+                } else {
+                    gui.painter.paint_region(&prs, d, everything);
+                }
+            },
+        )
+    };
+    let (runs, single) = (render(false), render(true));
+    for y in 0..240 {
+        for x in 0..320 {
+            assert_eq!(runs.get_color(x, y), single.get_color(x, y), "({x}, {y})");
+        }
+    }
+}
+
+#[test]
+#[ignore = "needs a display, e.g. run `xvfb-run cargo test -- --ignored --test-threads=1`"]
+fn texture_callbacks_draw_into_their_rect() {
+    use crate::paint::TextureCallback;
+    use std::sync::Arc;
+
+    let callback = Arc::new(TextureCallback::new(|info, d| {
+        assert_eq!(info.screen_size_px, [32, 16]);
+        d.clear_background(Color::RED);
+    }));
+    let run_ui = |ctx: &egui::Context| {
+        ctx.layer_painter(egui::LayerId::background())
+            .add(egui::PaintCallback {
+                rect: egui::Rect::from_min_size(egui::pos2(16.0, 8.0), egui::vec2(32.0, 16.0)),
+                callback: callback.clone(),
+            });
+    };
+    let image = render_offscreen(
+        (64, 64),
+        |rl, thread| {
+            let mut gui = RlEgui::default();
+            gui.set_strict_mode(true);
+            gui.prepare(rl, thread, run_ui);
+            gui.draw(&mut rl.begin_drawing(thread));
+            // The second frame reuses the first one's texture.
+            gui.prepare(rl, thread, run_ui);
+            gui
+        },
+        |gui, d| gui.draw(d),
+    );
+    assert_eq!(image.get_color(32, 16), Color::RED, "inside");
+    assert_eq!(image.get_color(32, 32), Color::BLACK, "outside");
+}
+
+#[test]
+#[ignore = "needs a display, e.g. run `xvfb-run cargo test -- --ignored --test-threads=1`"]
+fn undrawable_shapes_are_returned() {
+    use std::sync::Arc;
+
+    let mut failed = Vec::new();
+    let image = render_offscreen(
+        (64, 64),
+        |rl, thread| {
+            let mut gui = RlEgui::default();
+            // Collected shapes never reach the strict checks.
+            gui.set_strict_mode(true);
+            gui.prepare(rl, thread, |ctx| {
+                let painter = ctx.layer_painter(egui::LayerId::background());
+                let rect = egui::Rect::from_min_size(egui::pos2(0.0, 0.0), egui::vec2(32.0, 32.0));
+                let mut mesh = egui::Mesh::with_texture(egui::TextureId::User(99));
+                mesh.add_rect_with_uv(
+                    rect,
+                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                    Color32::WHITE,
+                );
+                painter.add(egui::Shape::Vec(vec![
+                    egui::Shape::rect_filled(rect, 0.0, Color32::RED),
+                    egui::Shape::mesh(mesh),
+                ]));
+                painter.add(egui::PaintCallback {
+                    rect,
+                    callback: Arc::new(()),
+                });
+            });
+            gui
+        },
+        |gui, d| failed = gui.draw_collecting(d),
+    );
+    assert_eq!(failed.len(), 2);
+    assert!(matches!(failed[0].shape, egui::Shape::Mesh(_)));
+    assert!(matches!(failed[1].shape, egui::Shape::Callback(_)));
+    // The rect beside the failed mesh is still drawn.
+    assert_eq!(image.get_color(16, 16), Color::RED);
+}
+
+#[test]
+fn resize_cursors_keep_their_direction() {
+    use crate::util::ConvertRE;
+    use egui::CursorIcon;
+    use raylib::ffi::MouseCursor;
+
+    // Window edges and corners, panel dividers, and table columns and rows.
+    for (icons, cursor) in [
+        (
+            &[
+                CursorIcon::ResizeHorizontal,
+                CursorIcon::ResizeEast,
+                CursorIcon::ResizeWest,
+                CursorIcon::ResizeColumn,
+            ][..],
+            MouseCursor::MOUSE_CURSOR_RESIZE_EW,
+        ),
+        (
+            &[
+                CursorIcon::ResizeVertical,
+                CursorIcon::ResizeNorth,
+                CursorIcon::ResizeSouth,
+                CursorIcon::ResizeRow,
+            ],
+            MouseCursor::MOUSE_CURSOR_RESIZE_NS,
+        ),
+        (
+            &[
+                CursorIcon::ResizeNeSw,
+                CursorIcon::ResizeNorthEast,
+                CursorIcon::ResizeSouthWest,
+            ],
+            MouseCursor::MOUSE_CURSOR_RESIZE_NESW,
+        ),
+        (
+            &[
+                CursorIcon::ResizeNwSe,
+                CursorIcon::ResizeNorthWest,
+                CursorIcon::ResizeSouthEast,
+            ],
+            MouseCursor::MOUSE_CURSOR_RESIZE_NWSE,
+        ),
+    ] {
+        for &icon in icons {
+            assert_eq!(raylib_cursor(icon), Some(cursor), "{icon:?}");
+            let converted: Option<MouseCursor> = icon.convert();
+            assert_eq!(converted, Some(cursor), "{icon:?}");
+        }
+    }
+    assert_eq!(raylib_cursor(CursorIcon::None), None);
+}
+
+#[test]
+#[ignore = "needs a display, e.g. run `xvfb-run cargo test -- --ignored --test-threads=1`"]
+fn cursor_warp_is_not_motion() {
+    use raylib::prelude::WindowState;
+
+    let (mut rl, thread) = raylib::init().size(320, 240).title("Warp").build();
+    rl.set_window_state(WindowState::default().set_window_hidden(true));
+    let mut gui = RlEgui::default();
+    gui.set_keep_raw_input(true);
+    let target = egui::pos2(100.0, 80.0);
+    gui.prepare(&mut rl, &thread, |ctx| {
+        ctx.send_viewport_cmd(egui::ViewportCommand::CursorPosition(target))
+    });
+    drop(rl.begin_drawing(&thread));
+    gui.prepare(&mut rl, &thread, |_| {});
+
+    // The pointer lands on the target, without relative motion or positions along the way.
+    let raw_input = gui.last_raw_input().expect("raw input should be kept");
+    for event in &raw_input.events {
+        match event {
+            egui::Event::MouseMoved(delta) => panic!("warp moved the mouse by {delta:?}"),
+            egui::Event::PointerMoved(pos) => assert_eq!(*pos, target),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+#[ignore = "needs a display, e.g. run `xvfb-run cargo test -- --ignored --test-threads=1`"]
+fn scale_overrides_last_one_frame() {
+    use raylib::prelude::WindowState;
+
+    let (mut rl, thread) = raylib::init().size(320, 240).title("Scale").build();
+    rl.set_window_state(WindowState::default().set_window_hidden(true));
+    let mut gui = RlEgui::default();
+    let run_ui = |ctx: &Context| {
+        egui::CentralPanel::default().show(ctx, |ui| ui.label("Scaled"));
+    };
+
+    gui.try_prepare_scaled(
+        &mut rl,
+        &thread,
+        Some(2.0),
+        run_ui,
+        &mut crate::DummyHandler,
+    )
+    .expect("frame should prepare");
+    assert_eq!(gui.prs.as_ref().map(|p| p.pixels_per_point()), Some(2.0));
+    assert_eq!(gui.input_options().native_pixels_per_point, 1.0);
+
+    gui.prepare(&mut rl, &thread, run_ui);
+    assert_eq!(gui.prs.as_ref().map(|p| p.pixels_per_point()), Some(1.0));
+}
+
+#[test]
+#[ignore = "needs a display, e.g. run `xvfb-run cargo test -- --ignored --test-threads=1`"]
+fn app_draws_between_background_and_foreground() {
+    use egui::layers::Order;
+
+    let image = render_offscreen(
+        (64, 64),
+        |rl, thread| {
+            let mut gui = RlEgui::default();
+            gui.set_split_layers(true);
+            gui.prepare(rl, thread, |ctx| {
+                let size = egui::vec2(32.0, 64.0);
+                let left = egui::Rect::from_min_size(egui::pos2(0.0, 0.0), size);
+                let right = egui::Rect::from_min_size(egui::pos2(32.0, 0.0), size);
+                let hud = egui::LayerId::new(Order::Foreground, egui::Id::new("hud"));
+                ctx.layer_painter(egui::LayerId::background())
+                    .rect_filled(left, 0.0, Color32::RED);
+                ctx.layer_painter(hud)
+                    .rect_filled(right, 0.0, Color32::GREEN);
+            });
+            gui
+        },
+        |gui, d| {
+            gui.draw_background(d, Order::Middle);
+            // The app's scene, covering the whole target.
+            d.draw_rectangle(0, 0, 64, 64, Color::BLUE);
+            gui.draw_foreground(d, Order::Middle);
+            assert!(gui.prs.is_none());
+        },
+    );
+    assert_eq!(image.get_color(16, 32), Color::BLUE, "background");
+    assert_eq!(
+        image.get_color(48, 32),
+        Color::new(0, 255, 0, 255),
+        "foreground"
+    );
+}
+
+#[test]
+fn dropped_paths_without_file_name_are_kept() {
+    let file = dropped_file("/home/user/notes.txt");
+    assert_eq!(file.name, "notes.txt");
+    assert_eq!(
+        file.path.as_deref(),
+        Some(std::path::Path::new("/home/user/notes.txt"))
+    );
+
+    let root = dropped_file("/");
+    assert_eq!(root.name, "/");
+    let parent = dropped_file("assets/..");
+    assert_eq!(parent.name, "assets/..");
+    assert!(parent.path.is_some());
+}
+
+#[test]
+#[ignore = "needs a display, e.g. run `xvfb-run cargo test -- --ignored --test-threads=1`"]
+fn stacked_translucent_rects_composite_like_egui() {
+    // An inset shadow along the top edge of a pressed button, as layers of translucent black.
+    let base = Color32::from_rgb(120, 160, 200);
+    let shadows = [
+        (24.0, Color32::from_black_alpha(60)),
+        (16.0, Color32::from_black_alpha(60)),
+        (8.0, Color32::from_rgba_unmultiplied(40, 0, 80, 90)),
+    ];
+    let image = render_offscreen(
+        (64, 64),
+        |rl, thread| {
+            let mut gui = RlEgui::default();
+            gui.prepare(rl, thread, |ctx| {
+                let painter = ctx.layer_painter(egui::LayerId::background());
+                let rect =
+                    |height| egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(64.0, height));
+                painter.rect_filled(rect(64.0), 0.0, base);
+                for (height, color) in shadows {
+                    painter.rect_filled(rect(height), 0.0, color);
+                }
+            });
+            gui
+        },
+        |gui, d| gui.draw(d),
+    );
+
+    // Egui's colors are premultiplied, and composite as `src + dst * (1 - src_alpha)`.
+    let over = |dst: Color32, src: Color32| {
+        let [sr, sg, sb, sa] = src.to_array();
+        let [dr, dg, db, _] = dst.to_array();
+        let blend = |s: u8, d: u8| s + (d as f32 * (255 - sa) as f32 / 255.0).round() as u8;
+        Color32::from_rgb(blend(sr, dr), blend(sg, dg), blend(sb, db))
+    };
+    for (y, layers) in [(4, 3), (12, 2), (20, 1), (40, 0)] {
+        let expected = shadows[..layers]
+            .iter()
+            .fold(base, |dst, &(_, src)| over(dst, src));
+        let actual = image.get_color(32, y);
+        for (a, e) in [actual.r, actual.g, actual.b]
+            .into_iter()
+            .zip(expected.to_array())
+        {
+            // Colors are unmultiplied for raylib, which multiplies them again while blending.
+            assert!(
+                a.abs_diff(e) <= 2,
+                "at y = {y}: {actual:?}, expected {expected:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn atlas_regions_map_to_uv() {
+    use crate::paint::AtlasHandle;
+    let atlas = AtlasHandle {
+        id: egui::TextureId::User(3),
+        size: egui::vec2(128.0, 64.0),
+    };
+    let (id, uv) = atlas.sub(egui::Rect::from_min_size(
+        egui::pos2(32.0, 16.0),
+        egui::vec2(32.0, 16.0),
+    ));
+    assert_eq!(id, egui::TextureId::User(3));
+    assert_eq!(
+        uv,
+        egui::Rect::from_min_max(egui::pos2(0.25, 0.25), egui::pos2(0.5, 0.5))
+    );
+}
+
+#[test]
+#[ignore = "needs a display, e.g. run `xvfb-run cargo test -- --ignored --test-threads=1`"]
+fn atlas_regions_are_drawn() {
+    use raylib::prelude::Image;
+
+    let image = render_offscreen(
+        (64, 64),
+        |rl, thread| {
+            // Two one-pixel sprites side by side.
+            let mut image = Image::gen_image_color(2, 1, Color::RED);
+            image.draw_pixel(1, 0, Color::BLUE);
+            let texture = rl
+                .load_texture_from_image(thread, &image)
+                .expect("texture should load");
+            let mut gui = RlEgui::default();
+            let atlas = gui.register_atlas(texture);
+            gui.prepare(rl, thread, |ctx| {
+                let (id, uv) = atlas.sub(egui::Rect::from_min_size(
+                    egui::pos2(1.0, 0.0),
+                    egui::vec2(1.0, 1.0),
+                ));
+                egui::Area::new("sprite".into())
+                    .fixed_pos(egui::Pos2::ZERO)
+                    .show(ctx, |ui| {
+                        ui.add(egui::Image::new((id, egui::vec2(32.0, 32.0))).uv(uv))
+                    });
+            });
+            gui
+        },
+        |gui, d| gui.draw(d),
+    );
+    assert_eq!(image.get_color(16, 16), Color::BLUE);
+}
+
+#[test]
+#[ignore = "needs a display, e.g. run `xvfb-run cargo test -- --ignored --test-threads=1`"]
+fn registered_textures_are_drawn_whole() {
+    use raylib::prelude::Image;
+
+    let image = render_offscreen(
+        (64, 64),
+        |rl, thread| {
+            // Red on the left, blue on the right.
+            let mut image = Image::gen_image_color(2, 1, Color::RED);
+            image.draw_pixel(1, 0, Color::BLUE);
+            let texture = rl
+                .load_texture_from_image(thread, &image)
+                .expect("texture should load");
+            let mut gui = RlEgui::default();
+            let id = gui.register_texture(texture);
+            gui.prepare(rl, thread, |ctx| {
+                let rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(64.0, 32.0));
+                let mut shape = egui::epaint::RectShape::filled(rect, 0.0, Color32::WHITE);
+                shape.fill_texture_id = id;
+                shape.uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+                ctx.layer_painter(egui::LayerId::background()).add(shape);
+            });
+            gui
+        },
+        |gui, d| gui.draw(d),
+    );
+    assert_eq!(image.get_color(16, 16), Color::RED, "left");
+    assert_eq!(image.get_color(48, 16), Color::BLUE, "right");
+}
+
+#[test]
+fn tessellate_now_needs_no_window() {
+    let mut gui = RlEgui::default();
+    let shapes = gui.tessellate_now(|ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| ui.label("Measure me"));
+    });
+    let text = shapes.iter().find_map(|clipped| match &clipped.shape {
+        egui::Shape::Text(ts) => Some(ts.galley.text()),
+        _ => None,
+    });
+    assert_eq!(text, Some("Measure me"));
+    // The font atlas is kept for the next prepared frame.
+    assert!(gui
+        .deferred_textures
+        .set
+        .iter()
+        .any(|(id, _)| *id == egui::TextureId::default()));
+}
+
+#[test]
+fn letterbox_is_centered() {
+    // An ultrawide window gets bars on both sides.
+    let region = letterbox(egui::vec2(2560.0, 1080.0), 2.0);
+    assert_eq!(
+        (region.x, region.y, region.width, region.height),
+        (200.0, 0.0, 2160.0, 1080.0)
+    );
+    // A square one gets them above and below.
+    let region = letterbox(egui::vec2(800.0, 800.0), 2.0);
+    assert_eq!(
+        (region.x, region.y, region.width, region.height),
+        (0.0, 200.0, 800.0, 400.0)
+    );
+
+    let options = InputOptions {
+        letterbox_aspect: Some(16.0 / 9.0),
+        ..Default::default()
+    };
+    let region = options
+        .current_region(egui::vec2(2560.0, 1080.0))
+        .expect("letterbox should confine the gui");
+    assert!(pointer_in_region(egui::pos2(1280.0, 540.0), Some(region)));
+    assert!(!pointer_in_region(egui::pos2(100.0, 540.0), Some(region)));
+}
+
+#[test]
+fn drags_continue_outside_the_window() {
+    use raylib::prelude::Vector2;
+
+    let ctx = Context::default();
+    let mut value = 50.0f32;
+    let screen = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(200.0, 100.0));
+    let mut frame = |events: Vec<egui::Event>| {
+        let mut rect = egui::Rect::NOTHING;
+        let raw_input = egui::RawInput {
+            screen_rect: Some(screen),
+            events,
+            ..Default::default()
+        };
+        panel_frame(&ctx, raw_input, |ui| {
+            rect = ui.add(egui::Slider::new(&mut value, 0.0..=100.0)).rect;
+        });
+        rect
+    };
+    let mut outside = false;
+    let mut move_to = |pos: egui::Pos2, captured: bool, events: &mut Vec<egui::Event>| {
+        if track_pointer(screen.contains(pos), captured, &mut outside, events) {
+            pointer_motion_events(Vector2::new(pos.x, pos.y), Vector2::zero(), 1.0, 0, events);
+        }
+    };
+
+    let center = frame(Vec::new()).center();
+    let mut events = Vec::new();
+    move_to(center, false, &mut events);
+    events.push(egui::Event::PointerButton {
+        pos: center,
+        button: egui::PointerButton::Primary,
+        pressed: true,
+        modifiers: Default::default(),
+    });
+    frame(events);
+
+    // Past the right edge of the window, mid-drag.
+    let mut events = Vec::new();
+    move_to(egui::pos2(400.0, center.y), true, &mut events);
+    assert!(!events.contains(&egui::Event::PointerGone));
+    frame(events);
+    assert!(ctx.dragged_id().is_some(), "the drag should go on");
+
+    let mut events = vec![egui::Event::PointerButton {
+        pos: egui::pos2(400.0, center.y),
+        button: egui::PointerButton::Primary,
+        pressed: false,
+        modifiers: Default::default(),
+    }];
+    move_to(egui::pos2(400.0, center.y), false, &mut events);
+    assert_eq!(events.last(), Some(&egui::Event::PointerGone));
+    frame(events);
+    assert_eq!(value, 100.0);
+}
+
+#[test]
+#[ignore = "needs a display, e.g. run `xvfb-run cargo test -- --ignored --test-threads=1`"]
+fn large_uploads_are_only_refused_over_a_set_limit() {
+    use crate::error::EguiRaylibError;
+    use raylib::prelude::WindowState;
+
+    let (mut rl, thread) = raylib::init().size(64, 64).title("Upload").build();
+    rl.set_window_state(WindowState::default().set_window_hidden(true));
+    let output = || {
+        let mut output = egui::FullOutput::default();
+        let image = egui::ColorImage::new([2100, 1], Color32::RED);
+        output.textures_delta.set.push((
+            egui::TextureId::Managed(1),
+            egui::epaint::ImageDelta::full(image, egui::TextureOptions::NEAREST),
+        ));
+        output
+    };
+
+    // Without a limit set, larger textures than egui was told about are uploaded as before.
+    let mut gui = RlEgui::default();
+    gui.painter
+        .predraw(output(), &mut rl, &thread)
+        .expect("no upload should be refused without a limit");
+
+    // As set from `InputOptions::max_texture_size` while preparing.
+    gui.painter.max_texture_side = 2048;
+    assert!(matches!(
+        gui.painter.predraw(output(), &mut rl, &thread),
+        Err(EguiRaylibError::TextureUpload(_))
+    ));
+}
+
+#[test]
+#[ignore = "needs a display, e.g. run `xvfb-run cargo test -- --ignored --test-threads=1`"]
+fn clearing_textures_keeps_loaded_ones() {
+    use raylib::prelude::WindowState;
+
+    let (mut rl, thread) = raylib::init().size(64, 64).title("Clear").build();
+    rl.set_window_state(WindowState::default().set_window_hidden(true));
+    let mut gui = RlEgui::default();
+    let mut output = egui::FullOutput::default();
+    let (font_id, loaded_id) = (egui::TextureId::default(), egui::TextureId::Managed(1));
+    let font = egui::FontImage::new([4, 4]);
+    let font = egui::epaint::ImageDelta::full(font, egui::TextureOptions::LINEAR);
+    let image = egui::ColorImage::new([4, 4], Color32::RED);
+    let image = egui::epaint::ImageDelta::full(image, egui::TextureOptions::NEAREST);
+    output.textures_delta.set = vec![(font_id, font), (loaded_id, image)];
+    gui.painter.predraw(output, &mut rl, &thread).unwrap();
+
+    gui.clear_textures();
+    // Only the font atlas is dropped, and sent again with the next frame.
+    assert!(gui.reupload_font);
+    assert!(gui.painter.unregister_texture(font_id).is_none());
+    // egui never sends a texture from `Context::load_texture` again, so it must be kept.
+    assert!(gui.painter.unregister_texture(loaded_id).is_some());
+}
@@ -1,10 +1,10 @@
 //! Some handy conversion stuff between Raylib and Egui types.
+//!
+//! Most conversions are between types of two foreign crates, so the orphan rule rules out `From` impls for them.
+//! [ConvertRE] fills in for `From`, and [ConvertTo] names the target type where it can't be inferred.
+//! Types of this crate also implement `From`.
 
-/// A trait to convert between raylib and egui types.
-pub trait ConvertRE<T> {
-    /// Construct the corresponding type from this type's data.
-    fn convert(&self) -> T;
-}
+pub use crate::base::{ConvertRE, ConvertTo};
 
 impl ConvertRE<raylib::prelude::Vector2> for egui::Pos2 {
     fn convert(&self) -> raylib::prelude::Vector2 {
@@ -62,55 +62,49 @@ impl ConvertRE<raylib::prelude::Color> for egui::Color32 {
     }
 }
 
+/// Key colors of egui's [Visuals](egui::Visuals) as raylib colors, to theme raylib-drawn elements consistently with the gui.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaletteRl {
+    /// Background color of windows.
+    pub window_fill: raylib::prelude::Color,
+    /// Background color of panels.
+    pub panel_fill: raylib::prelude::Color,
+    /// Background color of selected text and widgets.
+    pub selection: raylib::prelude::Color,
+    /// Color of hyperlinks.
+    pub hyperlink: raylib::prelude::Color,
+    /// Background color used to separate elements, such as stripes in grids.
+    pub faint_bg: raylib::prelude::Color,
+    /// Very dark or very light background, such as that of text edits.
+    pub extreme_bg: raylib::prelude::Color,
+    /// Color of non-interactive text.
+    pub text: raylib::prelude::Color,
+}
+
+impl ConvertRE<PaletteRl> for egui::Visuals {
+    fn convert(&self) -> PaletteRl {
+        PaletteRl {
+            window_fill: self.window_fill.convert(),
+            panel_fill: self.panel_fill.convert(),
+            selection: self.selection.bg_fill.convert(),
+            hyperlink: self.hyperlink_color.convert(),
+            faint_bg: self.faint_bg_color.convert(),
+            extreme_bg: self.extreme_bg_color.convert(),
+            text: self.text_color().convert(),
+        }
+    }
+}
+
+impl From<&egui::Visuals> for PaletteRl {
+    fn from(visuals: &egui::Visuals) -> Self {
+        visuals.convert()
+    }
+}
+
 impl ConvertRE<Option<raylib::prelude::MouseCursor>> for egui::CursorIcon {
+    /// The cursor the painter shows for this icon, or `None` if the cursor should be hidden.
     fn convert(&self) -> Option<raylib::consts::MouseCursor> {
-        let v = match self {
-            egui::CursorIcon::Default => raylib::prelude::MouseCursor::MOUSE_CURSOR_DEFAULT,
-            egui::CursorIcon::ContextMenu => raylib::prelude::MouseCursor::MOUSE_CURSOR_ARROW,
-            egui::CursorIcon::Help => raylib::prelude::MouseCursor::MOUSE_CURSOR_POINTING_HAND,
-            egui::CursorIcon::PointingHand => {
-                raylib::prelude::MouseCursor::MOUSE_CURSOR_POINTING_HAND
-            }
-            egui::CursorIcon::Crosshair => raylib::prelude::MouseCursor::MOUSE_CURSOR_CROSSHAIR,
-            egui::CursorIcon::Text => raylib::prelude::MouseCursor::MOUSE_CURSOR_IBEAM,
-            egui::CursorIcon::VerticalText => raylib::prelude::MouseCursor::MOUSE_CURSOR_IBEAM,
-            egui::CursorIcon::NoDrop => raylib::prelude::MouseCursor::MOUSE_CURSOR_NOT_ALLOWED,
-            egui::CursorIcon::NotAllowed => raylib::prelude::MouseCursor::MOUSE_CURSOR_NOT_ALLOWED,
-            egui::CursorIcon::Grab => raylib::prelude::MouseCursor::MOUSE_CURSOR_ARROW,
-            egui::CursorIcon::Grabbing => raylib::prelude::MouseCursor::MOUSE_CURSOR_POINTING_HAND,
-            egui::CursorIcon::ResizeHorizontal => {
-                raylib::prelude::MouseCursor::MOUSE_CURSOR_RESIZE_EW
-            }
-            egui::CursorIcon::ResizeNeSw => raylib::prelude::MouseCursor::MOUSE_CURSOR_RESIZE_NESW,
-            egui::CursorIcon::ResizeNwSe => raylib::prelude::MouseCursor::MOUSE_CURSOR_RESIZE_NWSE,
-            egui::CursorIcon::ResizeVertical => {
-                raylib::prelude::MouseCursor::MOUSE_CURSOR_RESIZE_NS
-            }
-            egui::CursorIcon::ResizeEast => raylib::prelude::MouseCursor::MOUSE_CURSOR_RESIZE_EW,
-            egui::CursorIcon::ResizeSouthEast => {
-                raylib::prelude::MouseCursor::MOUSE_CURSOR_RESIZE_NWSE
-            }
-            egui::CursorIcon::ResizeSouth => raylib::prelude::MouseCursor::MOUSE_CURSOR_RESIZE_NS,
-            egui::CursorIcon::ResizeSouthWest => {
-                raylib::prelude::MouseCursor::MOUSE_CURSOR_RESIZE_NESW
-            }
-            egui::CursorIcon::ResizeWest => raylib::prelude::MouseCursor::MOUSE_CURSOR_RESIZE_EW,
-            egui::CursorIcon::ResizeNorthWest => {
-                raylib::prelude::MouseCursor::MOUSE_CURSOR_RESIZE_NWSE
-            }
-            egui::CursorIcon::ResizeNorth => raylib::prelude::MouseCursor::MOUSE_CURSOR_RESIZE_NS,
-            egui::CursorIcon::ResizeNorthEast => {
-                raylib::prelude::MouseCursor::MOUSE_CURSOR_RESIZE_NESW
-            }
-            egui::CursorIcon::ResizeColumn => raylib::prelude::MouseCursor::MOUSE_CURSOR_RESIZE_ALL,
-            egui::CursorIcon::ResizeRow => raylib::prelude::MouseCursor::MOUSE_CURSOR_RESIZE_ALL,
-
-            egui::CursorIcon::None => {
-                return None;
-            }
-            _ => raylib::prelude::MouseCursor::MOUSE_CURSOR_DEFAULT,
-        };
-        Some(v)
+        crate::paint::raylib_cursor(*self)
     }
 }
 
@@ -120,7 +114,6 @@ impl ConvertRE<Option<egui::Key>> for raylib::prelude::KeyboardKey {
         use egui::Key;
         use raylib::prelude::KeyboardKey;
         let v = match *self {
-            KeyboardKey::KEY_NULL => Key::Space,
             KeyboardKey::KEY_APOSTROPHE => Key::Quote,
             KeyboardKey::KEY_COMMA => Key::Comma,
             KeyboardKey::KEY_MINUS => Key::Minus,
@@ -212,7 +205,26 @@ impl ConvertRE<Option<egui::Key>> for raylib::prelude::KeyboardKey {
             KeyboardKey::KEY_KP_ENTER => Key::Enter,
             KeyboardKey::KEY_KP_EQUAL => Key::Equals,
             KeyboardKey::KEY_BACK => Key::Backspace,
-            _ => {
+            // Keys without an egui counterpart. Modifiers are reported through `Modifiers` instead.
+            KeyboardKey::KEY_NULL
+            | KeyboardKey::KEY_KP_MULTIPLY
+            | KeyboardKey::KEY_CAPS_LOCK
+            | KeyboardKey::KEY_SCROLL_LOCK
+            | KeyboardKey::KEY_NUM_LOCK
+            | KeyboardKey::KEY_PRINT_SCREEN
+            | KeyboardKey::KEY_PAUSE
+            | KeyboardKey::KEY_KB_MENU
+            | KeyboardKey::KEY_LEFT_SHIFT
+            | KeyboardKey::KEY_LEFT_CONTROL
+            | KeyboardKey::KEY_LEFT_ALT
+            | KeyboardKey::KEY_LEFT_SUPER
+            | KeyboardKey::KEY_RIGHT_SHIFT
+            | KeyboardKey::KEY_RIGHT_CONTROL
+            | KeyboardKey::KEY_RIGHT_ALT
+            | KeyboardKey::KEY_RIGHT_SUPER
+            | KeyboardKey::KEY_MENU
+            | KeyboardKey::KEY_VOLUME_UP
+            | KeyboardKey::KEY_VOLUME_DOWN => {
                 return None;
             }
         };
@@ -220,12 +232,88 @@ impl ConvertRE<Option<egui::Key>> for raylib::prelude::KeyboardKey {
     }
 }
 
+/// The physical key to report to egui for the raylib key `kk`.
+///
+/// Raylib's keys name physical positions on a US layout, so this is the same as the [ConvertRE] conversion for most keys.
+/// egui has no variants for the numeric keypad though, so keypad keys report no physical key.
+/// This tells them apart from main-row keys, which share the same logical key (e.g. [egui::Key::Num1]).
+pub fn physical_key(kk: raylib::prelude::KeyboardKey) -> Option<egui::Key> {
+    if is_keypad_key(kk) {
+        None
+    } else {
+        kk.convert()
+    }
+}
+
+/// Whether `kk` is on the numeric keypad.
+pub fn is_keypad_key(kk: raylib::prelude::KeyboardKey) -> bool {
+    use raylib::prelude::KeyboardKey;
+    matches!(
+        kk,
+        KeyboardKey::KEY_KP_0
+            | KeyboardKey::KEY_KP_1
+            | KeyboardKey::KEY_KP_2
+            | KeyboardKey::KEY_KP_3
+            | KeyboardKey::KEY_KP_4
+            | KeyboardKey::KEY_KP_5
+            | KeyboardKey::KEY_KP_6
+            | KeyboardKey::KEY_KP_7
+            | KeyboardKey::KEY_KP_8
+            | KeyboardKey::KEY_KP_9
+            | KeyboardKey::KEY_KP_DECIMAL
+            | KeyboardKey::KEY_KP_DIVIDE
+            | KeyboardKey::KEY_KP_MULTIPLY
+            | KeyboardKey::KEY_KP_SUBTRACT
+            | KeyboardKey::KEY_KP_ADD
+            | KeyboardKey::KEY_KP_ENTER
+            | KeyboardKey::KEY_KP_EQUAL
+    )
+}
+
+/// Error for pixel data whose length doesn't match the size of the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeError {
+    /// Number of bytes required by the image size.
+    pub expected: usize,
+    /// Number of bytes provided.
+    pub actual: usize,
+}
+
+impl std::fmt::Display for SizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "expected {} bytes of RGBA data, got {}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for SizeError {}
+
+/// Same as [rl_image_from_rgba], but checks that `rgba` holds exactly `size[0]*size[1]*4` bytes first.
+/// Prefer this for pixel data that comes from outside the crate.
+pub fn try_image_from_rgba(
+    size: [usize; 2],
+    rgba: &[u8],
+) -> Result<raylib::prelude::Image, SizeError> {
+    let expected = size[0] * size[1] * 4;
+    if rgba.len() != expected {
+        return Err(SizeError {
+            expected,
+            actual: rgba.len(),
+        });
+    }
+    Ok(rl_image_from_rgba(size, rgba))
+}
+
 /// Convert raw image (Uncompressed RGBA) of size `size`, stored in `rgba` into raylib [Image](raylib::texture::Image)
 /// # Safety
 /// Unsafe behaviour occurs if image created did not allocate enough pixels for RGBA writing.
 /// However, this function uses Raylib's `gen_image_color` to allocate an image before writing.
 /// Currently, Raylib's `GenImageColor` function will `calloc` for `size[0]*size[1]*4` bytes in RGBA format itself.
 /// Thus, hypothetically this function is always safe.
+/// It does however read `size[0]*size[1]*4` bytes from `rgba` without checking its length, see [try_image_from_rgba].
 #[allow(dead_code)]
 pub fn rl_image_from_rgba(size: [usize; 2], rgba: &[u8]) -> raylib::prelude::Image {
     use raylib::prelude::{Color, Image};
@@ -239,3 +327,24 @@ pub fn rl_image_from_rgba(size: [usize; 2], rgba: &[u8]) -> raylib::prelude::Ima
         Image::from_raw(raw)
     }
 }
+
+/// Scissor rect `(x, y, width, height)` in pixels for `rect` in points, at `pxpp` pixels per point,
+/// within a target (e.g. the screen) of `bounds` `(width, height)` pixels.
+/// The minimum is floored and the maximum ceiled, so that every pixel the rect touches is kept,
+/// and a rect nested in another (e.g. a scroll area within a scroll area) never gets a scissor reaching beyond its parent's.
+/// `None` if no pixel is left, e.g. for empty, inverted or off-screen rects, as a scissor of negative size may crash or clip nothing at all.
+pub fn egui_rect_to_scissor(
+    rect: egui::Rect,
+    pxpp: f32,
+    bounds: (i32, i32),
+) -> Option<(i32, i32, i32, i32)> {
+    if !rect.is_positive() {
+        return None;
+    }
+    // Float to int casts saturate, so infinite rects (e.g. `Rect::EVERYTHING`) work too.
+    let x0 = ((rect.min.x * pxpp).floor() as i32).max(0);
+    let y0 = ((rect.min.y * pxpp).floor() as i32).max(0);
+    let x1 = ((rect.max.x * pxpp).ceil() as i32).min(bounds.0);
+    let y1 = ((rect.max.y * pxpp).ceil() as i32).min(bounds.1);
+    (x1 > x0 && y1 > y0).then_some((x0, y0, x1 - x0, y1 - y0))
+}